@@ -0,0 +1,90 @@
+// Compares the integer compositing in `Screen::bitblt` with the floating point version it replaced.
+// Run with `cargo bench --bench bitblt`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use engine2d::offscreen::Offscreen;
+use engine2d::texture::Texture;
+use engine2d::types::{Rect, Rgba, Vec2i};
+use image::{imageops, RgbaImage};
+
+const SIZE: usize = 480;
+
+// The old per-pixel "over", kept here as the baseline
+fn float_over(to: &mut [u8], from: &[u8]) {
+    let ta = to[3] as f32 / 255.0;
+    let fa = from[3] as f32 / 255.0;
+    for i in 0..3 {
+        to[i] = from[i].saturating_add((to[i] as f32 * (1.0 - fa)).round() as u8);
+    }
+    to[3] = ((fa + ta * (1.0 - fa)) * 255.0).round() as u8;
+}
+
+fn float_blit(to: &mut [u8], from: &[u8]) {
+    for (to, from) in to.chunks_exact_mut(4).zip(from.chunks_exact(4)) {
+        float_over(to, from);
+    }
+}
+
+// A screenful of real sprites: mostly fully transparent or fully opaque, with soft edges here and there
+fn sprite_sheet() -> Texture {
+    let mut sheet = RgbaImage::new(SIZE as u32, SIZE as u32);
+    let sprites = [
+        "slug/slug.png",
+        "slug/enemy.png",
+        "llama/sprites/asteroid.png",
+    ];
+    let mut y = 0;
+    for (row, path) in sprites.iter().cycle().enumerate() {
+        let img = image::open(path).expect("run from engine2d/").into_rgba8();
+        if y >= SIZE as u32 {
+            break;
+        }
+        let mut x = (row as u32 * 7) % img.width();
+        while x < SIZE as u32 {
+            imageops::overlay(&mut sheet, &img, x, y);
+            x += img.width();
+        }
+        y += img.height();
+    }
+    Texture::new(sheet)
+}
+
+// Every pixel partly see-through, so nothing can be skipped or copied
+fn translucent() -> Texture {
+    Texture::new(RgbaImage::from_fn(SIZE as u32, SIZE as u32, |x, y| {
+        image::Rgba([
+            (x % 256) as u8,
+            (y % 256) as u8,
+            90,
+            (1 + (x + y) % 254) as u8,
+        ])
+    }))
+}
+
+fn bench(c: &mut Criterion) {
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        w: SIZE as u16,
+        h: SIZE as u16,
+    };
+    for (name, tex) in [("sprites", sprite_sheet()), ("translucent", translucent())].iter() {
+        let mut group = c.benchmark_group(format!("over_480x480_{}", name));
+        let mut offscreen = Offscreen::new(SIZE, SIZE);
+        offscreen.screen(Vec2i(0, 0)).clear(Rgba(80, 80, 80, 255));
+        let mut before = offscreen.buffer().to_vec();
+        group.bench_function("float", |b| {
+            b.iter(|| float_blit(black_box(&mut before), black_box(tex.buffer())))
+        });
+        group.bench_function("integer", |b| {
+            b.iter(|| {
+                offscreen
+                    .screen(Vec2i(0, 0))
+                    .bitblt(black_box(tex), whole, Vec2i(0, 0))
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
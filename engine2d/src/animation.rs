@@ -1,3 +1,5 @@
+use crate::error::{Error, Result};
+use crate::texture::Texture;
 use crate::types::AnimationState;
 
 pub struct Animation {
@@ -14,6 +16,27 @@ impl Animation {
     pub fn new(states: Vec<AnimationState>) -> Self {
         Self { states, index: 0 }
     }
+
+    /// Check that every state has frames and every frame lies inside `texture`,
+    /// so a bad rect gets caught when the sprite is made instead of when it's drawn
+    pub fn validate(&self, texture: &Texture) -> Result<()> {
+        for (state, anim) in self.states.iter().enumerate() {
+            if anim.frames.is_empty() {
+                return Err(Error::NoFrames { state });
+            }
+            for (frame, &rect) in anim.frames.iter().enumerate() {
+                if !texture.valid_frame(rect) {
+                    return Err(Error::BadFrame {
+                        state,
+                        frame,
+                        rect,
+                        size: texture.size(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 pub trait StartAnim {
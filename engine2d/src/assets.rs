@@ -0,0 +1,59 @@
+use crate::error::Result;
+use crate::recolor::Recolor;
+use crate::texture::Texture;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Hands out shared textures, so each image file only gets decoded (and stored) once
+#[derive(Default)]
+pub struct Assets {
+    textures: HashMap<String, Rc<Texture>>,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The texture in the file at `path`, loading it the first time it's asked for
+    pub fn texture(&mut self, path: &Path) -> Result<Rc<Texture>> {
+        let key = path.to_string_lossy().into_owned();
+        if let Some(tex) = self.textures.get(&key) {
+            return Ok(Rc::clone(tex));
+        }
+        let tex = Rc::new(Texture::with_file(path)?);
+        self.textures.insert(key, Rc::clone(&tex));
+        Ok(tex)
+    }
+
+    /// The texture at `path` recolored, made the first time it's asked for and kept under `key` after that
+    pub fn recolored(&mut self, path: &Path, key: &str, recolor: &Recolor) -> Result<Rc<Texture>> {
+        if let Some(tex) = self.get(key) {
+            return Ok(tex);
+        }
+        let tex = self.texture(path)?.recolored(recolor);
+        Ok(self.insert(key, tex))
+    }
+
+    /// Store a texture that didn't come straight from a file under `key`, replacing anything already there
+    pub fn insert(&mut self, key: &str, tex: Texture) -> Rc<Texture> {
+        let tex = Rc::new(tex);
+        self.textures.insert(key.to_string(), Rc::clone(&tex));
+        tex
+    }
+
+    /// A texture we already have, by path or key
+    pub fn get(&self, key: &str) -> Option<Rc<Texture>> {
+        self.textures.get(key).map(Rc::clone)
+    }
+
+    /// How many distinct textures we're holding on to
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
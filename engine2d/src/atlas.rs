@@ -0,0 +1,276 @@
+use crate::error::{Error, Result};
+use crate::texture::Texture;
+use crate::types::Rect;
+use image::{self, RgbaImage};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Collects images by name and packs them into one atlas
+pub struct AtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+    /// Empty pixels left right of and below every image, so a frame that's a pixel too big never shows a neighbour
+    pub padding: u32,
+    /// How wide the atlas should be; by default it's picked to make the atlas roughly square.
+    /// It's never narrower than the widest image.
+    pub width: Option<u32>,
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        Self {
+            images: vec![],
+            padding: 1,
+            width: None,
+        }
+    }
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an image under `name`, replacing any image already called that
+    pub fn add(&mut self, name: &str, image: RgbaImage) {
+        match self.images.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = image,
+            None => self.images.push((name.to_string(), image)),
+        }
+    }
+
+    /// Load an image file and add it under `name`
+    pub fn add_file(&mut self, name: &str, path: &Path) -> Result<()> {
+        let image = image::open(path).map_err(|source| Error::Image {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.add(name, image.into_rgba8());
+        Ok(())
+    }
+
+    /// Lay out and copy every image into one big one (straight alpha, just like the files were),
+    /// along with where each image ended up. This is what you'd save out at build time.
+    pub fn pack_image(&self) -> (RgbaImage, HashMap<String, Rect>) {
+        let pad = self.padding;
+        let cells: Vec<(u32, u32)> = self
+            .images
+            .iter()
+            .map(|(_, img)| (img.width() + pad, img.height() + pad))
+            .collect();
+        let widest = self
+            .images
+            .iter()
+            .map(|(_, img)| img.width())
+            .max()
+            .unwrap_or(0);
+        let width = match self.width {
+            Some(w) => w.max(widest),
+            None => {
+                let area: u64 = cells.iter().map(|&(w, h)| w as u64 * h as u64).sum();
+                ((area as f64).sqrt().ceil() as u32).max(widest)
+            }
+        };
+        // The padding of the rightmost/bottommost images may hang off the edge
+        let (spots, height) = skyline_pack(&cells, width + pad);
+        let mut atlas = RgbaImage::new(width, height.saturating_sub(pad));
+        let mut rects = HashMap::new();
+        for ((name, img), (x, y)) in self.images.iter().zip(spots) {
+            image::imageops::replace(&mut atlas, img, x, y);
+            rects.insert(
+                name.clone(),
+                Rect {
+                    x: x as i32,
+                    y: y as i32,
+                    w: img.width() as u16,
+                    h: img.height() as u16,
+                },
+            );
+        }
+        (atlas, rects)
+    }
+
+    /// Pack everything into a texture, ready to draw from
+    pub fn build(&self) -> Atlas {
+        let (image, rects) = self.pack_image();
+        Atlas::new(&Rc::new(Texture::new(image)), rects)
+    }
+}
+
+/// One texture holding many images, each findable by name
+pub struct Atlas {
+    pub texture: Rc<Texture>,
+    rects: HashMap<String, Rect>,
+}
+
+impl Atlas {
+    pub fn new(texture: &Rc<Texture>, rects: HashMap<String, Rect>) -> Self {
+        Self {
+            texture: Rc::clone(texture),
+            rects,
+        }
+    }
+
+    /// Read the rects back from a manifest written by `Atlas::manifest`,
+    /// for an atlas image that was packed ahead of time
+    pub fn from_manifest(texture: &Rc<Texture>, manifest: &str) -> Result<Self> {
+        let mut rects = HashMap::new();
+        for (i, line) in manifest.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            let err = |message: String| Error::Manifest {
+                line: i + 1,
+                message,
+            };
+            if words.len() != 5 {
+                return Err(err(format!("expected `name x y w h`, got {:?}", line)));
+            }
+            let num = |word: &str| -> Result<i32> {
+                word.parse()
+                    .map_err(|_| err(format!("bad number {:?}", word)))
+            };
+            let (w, h) = (num(words[3])?, num(words[4])?);
+            if w < 0 || h < 0 {
+                return Err(err(format!("negative size {}x{}", w, h)));
+            }
+            rects.insert(
+                words[0].to_string(),
+                Rect {
+                    x: num(words[1])?,
+                    y: num(words[2])?,
+                    w: w as u16,
+                    h: h as u16,
+                },
+            );
+        }
+        Ok(Self::new(texture, rects))
+    }
+
+    /// Write out where each image is, one `name x y w h` line each (sorted by name)
+    pub fn manifest(rects: &HashMap<String, Rect>) -> String {
+        let mut names: Vec<&String> = rects.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let r = rects[name];
+                format!("{} {} {} {} {}\n", name, r.x, r.y, r.w, r.h)
+            })
+            .collect()
+    }
+
+    /// Where the whole of image `name` is in the atlas
+    pub fn rect(&self, name: &str) -> Option<Rect> {
+        self.rects.get(name).copied()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.rects.keys().map(|n| n.as_str())
+    }
+
+    /// Move `frames`, given relative to the original image `name`, to where that image is in the atlas.
+    /// Handy for `AnimationState::frames`.
+    pub fn frames(&self, name: &str, frames: &[Rect]) -> Option<Vec<Rect>> {
+        let r = self.rect(name)?;
+        Some(
+            frames
+                .iter()
+                .map(|f| Rect {
+                    x: f.x + r.x,
+                    y: f.y + r.y,
+                    ..*f
+                })
+                .collect(),
+        )
+    }
+
+    /// Cut image `name` into a grid of `w` by `h` cells, row by row.
+    /// Handy for `Tileset::with_rects`, or for a sprite sheet of same-sized frames.
+    pub fn grid(&self, name: &str, w: u16, h: u16) -> Option<Vec<Rect>> {
+        let r = self.rect(name)?;
+        let (cols, rows) = (r.w / w.max(1), r.h / h.max(1));
+        let mut cells = vec![];
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(Rect {
+                    x: r.x + (col * w) as i32,
+                    y: r.y + (row * h) as i32,
+                    w,
+                    h,
+                });
+            }
+        }
+        Some(cells)
+    }
+}
+
+/// Bottom-left skyline packing: tallest boxes first, each one going wherever its top edge ends up lowest.
+/// Returns where each box (in the original order) goes and how tall the result is.
+fn skyline_pack(sizes: &[(u32, u32)], width: u32) -> (Vec<(u32, u32)>, u32) {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| {
+        sizes[b]
+            .1
+            .cmp(&sizes[a].1)
+            .then(sizes[b].0.cmp(&sizes[a].0))
+    });
+    // The top of everything placed so far, as (x, y, width) segments left to right
+    let mut skyline = vec![(0, 0, width)];
+    let mut spots = vec![(0, 0); sizes.len()];
+    let mut height = 0;
+    for i in order {
+        let (w, h) = sizes[i];
+        // Find the segment to start at which puts the box's top lowest, breaking ties to the left
+        let mut best: Option<(usize, u32, u32)> = None;
+        for start in 0..skyline.len() {
+            let x = skyline[start].0;
+            if x + w > width {
+                break;
+            }
+            let mut y = 0;
+            let mut covered = 0;
+            for seg in &skyline[start..] {
+                if covered >= w {
+                    break;
+                }
+                y = y.max(seg.1);
+                covered += seg.2;
+            }
+            if best.map_or(true, |(_, _, by)| y < by) {
+                best = Some((start, x, y));
+            }
+        }
+        let (start, x, y) = best.expect("box is wider than the atlas");
+        spots[i] = (x, y);
+        height = height.max(y + h);
+        // Raise the skyline under the box, trimming whatever it now covers
+        skyline.insert(start, (x, y + h, w));
+        let right = x + w;
+        while start + 1 < skyline.len() {
+            let next = &mut skyline[start + 1];
+            if next.0 >= right {
+                break;
+            }
+            let overlap = right - next.0;
+            if next.2 <= overlap {
+                skyline.remove(start + 1);
+            } else {
+                next.0 += overlap;
+                next.2 -= overlap;
+                break;
+            }
+        }
+        // Neighbours at the same height are really one segment
+        skyline.dedup_by(|b, a| {
+            if a.1 == b.1 {
+                a.2 += b.2;
+                true
+            } else {
+                false
+            }
+        });
+    }
+    (spots, height)
+}
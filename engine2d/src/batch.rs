@@ -0,0 +1,120 @@
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::*;
+use rayon::prelude::*;
+
+/// One textured draw with everything worked out ahead of time, so it can be done on any thread
+#[derive(Clone, Copy)]
+pub struct Blit<'t> {
+    pub texture: &'t Texture,
+    pub from: Rect,
+    /// Where the top-left of `from` goes, in world coordinates (or screen coordinates if `hud` is set)
+    pub to: Vec2i,
+    /// Rotation and scaling, if there's any
+    pub transform: Option<Transform>,
+    pub params: DrawParams,
+    pub hud: bool,
+}
+
+impl<'t> Blit<'t> {
+    pub fn draw(&self, screen: &mut Screen) {
+        // Undo the camera offset for HUD blits
+        let to = if self.hud {
+            Vec2i(self.to.0 + screen.position.0, self.to.1 + screen.position.1)
+        } else {
+            self.to
+        };
+        match self.transform {
+            None => screen.bitblt_with(self.texture, self.from, to, self.params),
+            Some(transform) => {
+                screen.bitblt_transformed(self.texture, self.from, to, transform, self.params)
+            }
+        }
+    }
+}
+
+/// A pile of blits drawn in the order they were pushed.
+/// `draw_parallel` splits the screen into horizontal bands and fills them in on all the cores at once;
+/// every pixel still sees the same blits in the same order, so the picture comes out exactly the same.
+#[derive(Default, Clone)]
+pub struct Batch<'t> {
+    blits: Vec<Blit<'t>>,
+}
+
+impl<'t> Batch<'t> {
+    pub fn new() -> Self {
+        Self { blits: vec![] }
+    }
+
+    pub fn push(&mut self, blit: Blit<'t>) {
+        self.blits.push(blit);
+    }
+
+    pub fn len(&self) -> usize {
+        self.blits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blits.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.blits.clear();
+    }
+
+    /// Draw every blit in order on this thread
+    pub fn draw(&self, screen: &mut Screen) {
+        for blit in self.blits.iter() {
+            blit.draw(screen);
+        }
+    }
+
+    /// Draw every blit in order, one band of rows per thread
+    pub fn draw_parallel(&self, screen: &mut Screen) {
+        let threads = rayon::current_num_threads();
+        let rows = (screen.bounds().h as usize + threads - 1) / threads;
+        self.draw_banded(screen, rows);
+    }
+
+    /// Like `draw_parallel`, but with bands of `rows` rows each
+    pub fn draw_banded(&self, screen: &mut Screen, rows: usize) {
+        // Bands have their own idea of where the camera is, so HUD blits get placed up front
+        let camera = screen.position;
+        let blits: Vec<Blit> = self
+            .blits
+            .iter()
+            .map(|blit| Blit {
+                to: if blit.hud {
+                    Vec2i(blit.to.0 + camera.0, blit.to.1 + camera.1)
+                } else {
+                    blit.to
+                },
+                hud: false,
+                ..*blit
+            })
+            .collect();
+        // Bands don't keep a log, so log the blits here first without drawing them
+        let clip = screen.clip();
+        if screen.is_logging() {
+            screen.set_clip(Rect { w: 0, h: 0, ..clip });
+            for blit in blits.iter() {
+                blit.draw(screen);
+            }
+            screen.set_clip(clip);
+        }
+        if clip.is_empty() {
+            return;
+        }
+        screen.bands(rows).into_par_iter().for_each(|mut band| {
+            for blit in blits.iter() {
+                blit.draw(&mut band);
+            }
+        });
+    }
+}
+
+impl<'t> Extend<Blit<'t>> for Batch<'t> {
+    fn extend<I: IntoIterator<Item = Blit<'t>>>(&mut self, iter: I) {
+        self.blits.extend(iter);
+    }
+}
@@ -0,0 +1,40 @@
+use engine2d::atlas::{Atlas, AtlasBuilder};
+use image::ImageFormat;
+use std::fs;
+use std::path::Path;
+
+// `atlas out.png a.png b.png ...` packs the images into out.png and writes out.txt next to it,
+// with one `name x y w h` line per image (named after the file, minus its extension).
+// Load them back with `Atlas::from_manifest`.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: {} <atlas.png> <image>...", args[0]);
+        std::process::exit(2);
+    }
+    if let Err(e) = pack(Path::new(&args[1]), &args[2..]) {
+        eprintln!("Couldn't pack atlas: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn pack(out: &Path, inputs: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = AtlasBuilder::new();
+    for input in inputs {
+        let path = Path::new(input);
+        let name = path
+            .file_stem()
+            .map_or(input.clone(), |s| s.to_string_lossy().into_owned());
+        builder.add_file(&name, path)?;
+    }
+    let (image, rects) = builder.pack_image();
+    image.save_with_format(out, ImageFormat::Png)?;
+    fs::write(out.with_extension("txt"), Atlas::manifest(&rects))?;
+    println!(
+        "Packed {} images into {}x{}",
+        rects.len(),
+        image.width(),
+        image.height()
+    );
+    Ok(())
+}
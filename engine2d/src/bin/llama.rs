@@ -3,21 +3,30 @@ use std::fs;
 use std::path::Path;
 use std::rc::Rc;
 use std::time::Instant;
-use std::{thread, time};
 use winit::dpi::LogicalSize;
 use winit::event::{Event, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
 
-
+use engine2d::animation::*;
+use engine2d::assets::Assets;
+use engine2d::camera::Camera;
+use engine2d::collision::*;
+use engine2d::dirty::DirtyRects;
+use engine2d::error::Error;
+use engine2d::font::*;
 use engine2d::llama_maker::*;
-use engine2d::tile::*;
+use engine2d::offscreen::Offscreen;
+use engine2d::parallax::Parallax;
+use engine2d::particles::Emitter;
+use engine2d::render_queue::*;
 use engine2d::screen::Screen;
-use engine2d::collision::*;
-use engine2d::texture::Texture;
-use engine2d::animation::*;
 use engine2d::sprite::*;
+use engine2d::terrain::Terrain;
+use engine2d::texture::Texture;
+use engine2d::tile::*;
+use engine2d::transition::*;
 use engine2d::types::*;
 
 // Now this main module is just for the run-loop and rules processing.
@@ -26,7 +35,17 @@ struct GameState {
     // Colliders?  Sprites and stuff?
     textures: Vec<Rc<Texture>>,
     sprites: Vec<Sprite>,
-    scroll: Vec2i,
+    // Big asteroids that lasers chip bits off of
+    rocks: Vec<Terrain>,
+    camera: Camera,
+    // Frames left before the game over screen, once the player's been hit
+    dying: usize,
+    // The level (and whether it's the "next level" screen) last drawn, so we notice when it changes
+    scene: (u16, bool),
+    transition: Option<Transition>,
+    // Shared by every asteroid that blows up
+    explosions: Vec<Emitter>,
+    exhaust: Emitter,
     level: u16,
     current_tex: usize,
     shots_left: i32,
@@ -34,6 +53,10 @@ struct GameState {
     shot_index: usize,
     frame: usize,
     next_level: bool,
+    show_hit_boxes: bool,
+    font: BitmapFont,
+    // Every texture we've loaded so far, so levels can share them
+    assets: Assets,
 }
 // seconds per frame
 const DT: f64 = 1.0 / 60.0;
@@ -43,6 +66,24 @@ const HEIGHT: usize = 240;
 const DEPTH: usize = 4;
 
 fn main() {
+    // `llama --screenshot out.png` renders the start of level 1 to a PNG without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "--screenshot" {
+        if let Err(e) = screenshot(Path::new(&args[2])) {
+            eprintln!("Couldn't take screenshot: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Load everything up front, so a missing asset is reported before a window opens
+    let (mut state, backdrop) = match init() {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Couldn't load the game: {}", e);
+            eprintln!("(assets are loaded relative to the working directory; run from engine2d/)");
+            std::process::exit(1);
+        }
+    };
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
     let window = {
@@ -60,110 +101,59 @@ fn main() {
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
         Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture).unwrap()
     };
-    let mut state = GameState {
-        // initial game state...
-        sprites: vec![],
-        textures: make_menus(),
-        scroll: Vec2i(0, 0),
-        level: 0,
-        current_tex: 0,
-        shots_left: 3,
-        shot_cool_down: 0,
-        shot_index: 0,
-        frame: 0,
-        next_level: false,
-    };
-    let tex = Rc::new(Texture::with_file(Path::new("llama/space_tileset.png")));
-    let tileset = Rc::new(Tileset::new(
-        vec![
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-            Tile { solid: false },
-        ],
-        &tex,
-    ));
-    let maps = vec![Tilemap::new(Vec2i(0, 0), (64, 6), &tileset, make_map())];
     // How many frames have we simulated?
     let mut frame_count: usize = 0;
     // How many unsimulated frames have we saved up?
     let mut available_time = 0.0;
     // Track end of the last frame
     let mut since = Instant::now();
+    // Only the parts of the screen that changed get redrawn
+    let mut dirty = DirtyRects::new();
     event_loop.run(move |event, _, control_flow| {
         // Draw the current frame
         if let Event::RedrawRequested(_) = event {
             let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH, Vec2i(0, 0));
-            screen.clear(Rgba(255, 255, 255, 255));
-            screen.position = state.scroll;
-
             if state.level == 0 {
-                update_menu(&mut state, &input);
-                screen.bitblt(
-                    &state.textures[state.current_tex], // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 240,
-                        h: 240,
-                    },
-                    Vec2i(0, 0),
-                );
+                if let Err(e) = update_menu(&mut state, &input) {
+                    eprintln!("Couldn't load the level: {}", e);
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
             } else if state.level == 4 {
-                state.scroll = Vec2i(0, 0);
-                screen.bitblt(
-                    &state.textures[5], // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 240,
-                        h: 240,
-                    },
-                    Vec2i(0, 0),
-                );
+                state.camera.reset(Vec2i(0, 0));
             } else if state.level == 5 {
-                state.scroll = Vec2i(0, 0);
-                screen.bitblt(
-                    &state.textures[6], // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 240,
-                        h: 240,
-                    },
-                    Vec2i(0, 0),
-                );
-                if input.key_held(VirtualKeyCode::Return){
+                state.camera.reset(Vec2i(0, 0));
+                if input.key_held(VirtualKeyCode::Return) {
                     state.level = 0;
                     state.shots_left = 3;
                 }
             } else if state.next_level {
-                update_level(&mut state, &input);
-                state.scroll = Vec2i(0, 0);
-                screen.bitblt(
-                    &state.textures[4], // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 240,
-                        h: 240,
-                    },
-                    Vec2i(0, 0),
-                );
+                if let Err(e) = update_level(&mut state, &input) {
+                    eprintln!("Couldn't load the level: {}", e);
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                state.camera.reset(Vec2i(0, 0));
             } else {
-                for map in maps.iter() {
-                    map.draw(&mut screen);
+                for s in state.sprites.iter_mut() {
+                    s.animate(frame_count);
                 }
-                draw_game(&mut state, &mut screen, frame_count);
             }
+            screen.position = state.camera.offset();
+            // Switching screens starts a transition away from the last frame, which is still on screen
+            // until the new one gets drawn
+            let scene = (state.level, state.next_level);
+            if scene != state.scene {
+                state.scene = scene;
+                state.transition = Some(transition_to(scene));
+            }
+            if let Some(transition) = &mut state.transition {
+                transition.capture(&screen);
+            }
+            let changed = dirty.draw(&mut screen, |screen| draw_frame(&state, &backdrop, screen));
 
-            // Flip buffers
-
-            if pixels.render().is_err() {
+            // Flip buffers, unless it'd show just the same picture again
+            if changed && pixels.render().is_err() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
@@ -179,15 +169,28 @@ fn main() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
+            // F1 toggles the hit box debug overlay
+            if input.key_pressed(VirtualKeyCode::F1) {
+                state.show_hit_boxes = !state.show_hit_boxes;
+            }
             // Resize the window if needed
             if let Some(size) = input.window_resized() {
                 pixels.resize(size.width, size.height);
+                dirty.invalidate();
             }
         }
         // And the simulation "consumes" it
         while available_time >= DT {
             // Eat up one frame worth of time
             available_time -= DT;
+            // The game holds still while the screen changes
+            if let Some(transition) = &mut state.transition {
+                transition.update();
+                if transition.done() {
+                    state.transition = None;
+                }
+                continue;
+            }
             if !state.next_level && (state.level == 1 || state.level == 2 || state.level == 3) {
                 update_game(&mut state, &input);
                 // Increment the frame counter
@@ -202,21 +205,191 @@ fn main() {
     });
 }
 
-fn draw_game(state: &mut GameState, screen: &mut Screen, frame_number: usize) {
-    for s in state.sprites.iter_mut().rev() {
-        s.animate(frame_number);
-        screen.draw_sprite(s);
+// Everything the game needs before the first frame; fails if any asset can't be loaded
+fn init() -> Result<(GameState, Vec<Parallax>), Error> {
+    let mut assets = Assets::new();
+    let mut state = GameState {
+        // initial game state...
+        sprites: vec![],
+        rocks: vec![],
+        textures: make_menus(&mut assets)?,
+        camera: make_camera(),
+        dying: 0,
+        scene: (0, false),
+        transition: None,
+        explosions: make_explosion(&mut assets)?,
+        exhaust: make_exhaust(),
+        level: 0,
+        current_tex: 0,
+        shots_left: 3,
+        shot_cool_down: 0,
+        shot_index: 0,
+        frame: 0,
+        next_level: false,
+        show_hit_boxes: false,
+        font: make_font(&mut assets)?,
+        assets,
+    };
+    let backdrop = make_backdrop(&mut state.assets)?;
+    Ok((state, backdrop))
+}
+
+// How to get to each screen: levels open up out of an iris, the in-between screens fade in,
+// and the menus are swapped quickly
+fn transition_to((level, next_level): (u16, bool)) -> Transition {
+    let black = Rgba(0, 0, 0, 255);
+    match level {
+        _ if next_level => Transition::new(Effect::Fade(black), 60),
+        0 => Transition::new(Effect::Wipe, 30),
+        1..=3 => Transition::new(Effect::Iris(black), 40),
+        4 => Transition::new(Effect::Fade(Rgba(255, 255, 255, 255)), 60),
+        _ => Transition::new(Effect::Fade(black), 40),
+    }
+}
+
+// The view scrolls along with the player, who stays a little way in from the left edge.
+// It never moves vertically since the view is as tall as the playfield.
+fn make_camera() -> Camera {
+    let mut camera = Camera::new((WIDTH as u32, HEIGHT as u32));
+    camera.dead_zone = Rect {
+        x: 0,
+        y: 0,
+        w: 34,
+        h: HEIGHT as u16,
+    };
+    // The level is as long as the ground
+    camera.bounds = Some(Rect {
+        x: 0,
+        y: 0,
+        w: 64 * TILE_SZ,
+        h: HEIGHT as u16,
+    });
+    camera
+}
+
+// Draw the first frame of level 1 into an offscreen buffer and save it
+fn screenshot(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut state, backdrop) = init()?;
+    state.level = 1;
+    load_level(&mut state, 1)?;
+    let mut offscreen = Offscreen::new(WIDTH, HEIGHT);
+    let mut screen = offscreen.screen(state.camera.offset());
+    screen.clear(Rgba(255, 255, 255, 255));
+    for s in state.sprites.iter_mut() {
+        s.animate(0);
+    }
+    draw_game(&state, &backdrop, &mut screen);
+    offscreen.save_png(path)?;
+    Ok(())
+}
+
+// Menus fill the whole window, whatever the camera is up to
+fn draw_menu(screen: &mut Screen, menu: &Texture) {
+    // They're fully opaque, so they can just be copied in
+    let opaque = DrawParams {
+        blend: BlendMode::Copy,
+        ..DrawParams::default()
+    };
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        w: 240,
+        h: 240,
+    };
+    screen.hud(|screen| screen.bitblt_with(menu, whole, Vec2i(0, 0), opaque));
+}
+
+// Draw whatever the game's showing right now from scratch, without changing anything
+fn draw_frame(state: &GameState, backdrop: &[Parallax], screen: &mut Screen) {
+    screen.clear(Rgba(255, 255, 255, 255));
+    if state.level == 0 {
+        draw_menu(screen, &state.textures[state.current_tex]); // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
+    } else if state.level == 4 {
+        draw_menu(screen, &state.textures[5]); // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
+    } else if state.level == 5 {
+        draw_menu(screen, &state.textures[6]); // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
+    } else if state.next_level {
+        draw_menu(screen, &state.textures[4]); // TODO: JUST MAKE A FUNCTION TO PROVIDE THE TEXTURE
+    } else {
+        draw_game(state, backdrop, screen);
+    }
+    if let Some(transition) = &state.transition {
+        transition.draw(screen);
+    }
+}
+
+fn draw_game(state: &GameState, backdrop: &[Parallax], screen: &mut Screen) {
+    let mut queue = RenderQueue::new();
+    for (depth, layer) in backdrop.iter().enumerate() {
+        queue.parallax(Layer::Background, depth as i32, layer);
+    }
+    for (i, s) in state.sprites.iter().enumerate() {
+        let layer = if s.hud { Layer::Hud } else { Layer::World };
+        queue.sprite(layer, sprite_depth(i), s);
+    }
+    for rock in state.rocks.iter() {
+        queue.terrain(Layer::World, 0, rock);
+    }
+    // The exhaust comes out from under the llama
+    queue.particles(Layer::World, sprite_depth(0) - 1, &state.exhaust);
+    for emitter in state.explosions.iter() {
+        queue.particles(Layer::Effects, 0, emitter);
+    }
+    let level = format!("LEVEL {}", state.level);
+    let shots = format!("SHOTS {}", state.shots_left);
+    let font = &state.font;
+    queue.custom(Layer::Hud, 0, move |screen| {
+        font.draw(screen, &level, Vec2i(4, 4), TextStyle::default());
+        let right = TextStyle {
+            align: Align::Right,
+            ..TextStyle::default()
+        };
+        font.draw(screen, &shots, Vec2i(WIDTH as i32 - 4, 4), right);
+    });
+    if state.show_hit_boxes {
+        let sprites = &state.sprites;
+        queue.custom(Layer::Effects, 1, move |screen| {
+            for s in sprites.iter() {
+                for hit_box in s.hit_boxes.iter() {
+                    screen.draw_rect(*hit_box, Rgba(255, 0, 0, 255));
+                }
+            }
+        });
+    }
+    queue.draw(screen);
+}
+
+// How far in front each sprite is: the player is on top of the lasers, which are over the asteroids,
+// which pass in front of the ship
+fn sprite_depth(index: usize) -> i32 {
+    match index {
+        0 => 3,
+        1..=3 => 1,
+        5 => -1,
+        _ => 0,
     }
 }
 
 fn update_game(state: &mut GameState, input: &WinitInputHelper) {
+    // Once hit, everything freezes while the screen shakes, then it's game over
+    if state.dying > 0 {
+        state.dying -= 1;
+        if state.dying == 0 {
+            state.level = 5;
+        }
+        state.exhaust.rate = 0.0;
+        update_particles(state);
+        state.camera.update();
+        return;
+    }
+
     // Player control goes here
 
     let bottom_border = 165;
-    if input.key_held(VirtualKeyCode::Up)
+    let thrusting = input.key_held(VirtualKeyCode::Up)
         && !(state.sprites[0].position.1 > bottom_border)
-        && !(state.sprites[0].position.1 < 0)
-    {
+        && !(state.sprites[0].position.1 < 0);
+    if thrusting {
         if state.sprites[0].vy > 0.0 {
             state.sprites[0].vy /= 3.0;
         }
@@ -246,14 +419,16 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper) {
     // Scroll & Movement
     if state.level == 1 || state.level == 2 || state.level == 3 {
         // Gather collisions for player
-        if player_contacts(&state.sprites, 4) {
-            thread::sleep(time::Duration::from_millis(500));
-            state.level = 5;
+        let hit_rock = state
+            .rocks
+            .iter()
+            .any(|rock| terrain_contact(&state.sprites[0], rock));
+        if player_contacts(&state.sprites, 4) || hit_rock {
+            state.camera.add_trauma(1.0);
+            state.dying = 30;
+            return;
         }
 
-        // Scroll camera
-        state.scroll = Vec2i(state.scroll.0 + 2, state.scroll.1);
-
         // Move Sprite
         state.sprites[0].position.0 += 2;
         state.sprites[0].position.1 += state.sprites[0].vy.min(2.0) as i32;
@@ -263,6 +438,8 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper) {
             hit_box.x += 2;
             hit_box.y = y_pos;
         }
+        // Scroll camera
+        state.camera.follow(&state.sprites[0]);
 
         // Move lasers
         for i in 1..4 {
@@ -272,7 +449,7 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper) {
             }
             // Move laser out of screen so it doesn't collide with obstacles that haven't loaded yet
             // Once out of the screen update our shot
-            if state.sprites[i].position.0 == (state.scroll.0 + 240) {
+            if state.sprites[i].position.0 == (state.camera.position().0 + 240) {
                 state.sprites[i].position.1 = -20;
                 state.sprites[i].hit_boxes[0].y = -20;
                 state.sprites[i].hit_boxes[0].x = -20;
@@ -282,17 +459,60 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper) {
                 }
             }
         }
-        // Move battery
-        state.sprites[4].position.0 += 2;
+        // Bank the ship back and forth
+        state.sprites[5].rotation = 0.15 * (state.frame as f32 / 40.0).sin();
+        // Tumble the asteroids, alternating directions so they don't all spin in lockstep
+        for (i, s) in state.sprites.iter_mut().enumerate().skip(6) {
+            s.rotation += if i % 2 == 0 { 0.02 } else { -0.03 };
+        }
         state.shot_cool_down = (state.shot_cool_down - 1).max(0);
 
-        laser_contacts(&mut state.sprites, state.frame);
+        // Every asteroid that blows up gives the screen a jolt
+        for Vec2i(x, y) in laser_contacts(&mut state.sprites, state.frame) {
+            state.camera.add_trauma(0.4);
+            for emitter in state.explosions.iter_mut() {
+                emitter.position = Vec2i(x + 16, y + 16);
+                emitter.burst(20);
+            }
+        }
+        // Lasers take a bite out of whichever big asteroid they run into
+        for i in 1..4 {
+            let hit_box = state.sprites[i].hit_boxes[0];
+            let hit = state
+                .rocks
+                .iter_mut()
+                .find_map(|rock| rock.first_solid(hit_box).map(|at| (rock, at)));
+            if let Some((rock, Vec2i(x, y))) = hit {
+                rock.carve_circle(Vec2i(x + 3, y), 7);
+                state.sprites[i].position.1 = -200;
+                state.sprites[i].hit_boxes[0].y = -20;
+                state.sprites[i].hit_boxes[0].x = -20;
+                state.camera.add_trauma(0.1);
+                for emitter in state.explosions.iter_mut() {
+                    emitter.position = Vec2i(x, y);
+                    emitter.burst(4);
+                }
+            }
+        }
+        // The jetpack sits low on the llama's back
+        let Vec2i(x, y) = state.sprites[0].position;
+        state.exhaust.position = Vec2i(x + 12, y + 40);
+        state.exhaust.rate = if thrusting { 1.5 } else { 0.0 };
+        update_particles(state);
+        state.camera.update();
         check_clear(state);
     }
 }
 
+fn update_particles(state: &mut GameState) {
+    state.exhaust.update();
+    for emitter in state.explosions.iter_mut() {
+        emitter.update();
+    }
+}
+
 // Function that takes care of moving around in the menu
-fn update_menu(state: &mut GameState, input: &WinitInputHelper) {
+fn update_menu(state: &mut GameState, input: &WinitInputHelper) -> Result<(), Error> {
     if input.key_pressed(VirtualKeyCode::Up) && state.current_tex != 3 {
         if state.current_tex != 0 {
             state.current_tex -= 1;
@@ -309,18 +529,16 @@ fn update_menu(state: &mut GameState, input: &WinitInputHelper) {
         if state.current_tex == 0 {
             fs::write("llama/save.txt", "0");
             state.level = 1;
-            state.sprites = level_1();
-            for s in state.sprites.iter_mut() {
-                s.animation.set_state(0, state.frame)
-            }
+            load_level(state, 1)?;
         } else if state.current_tex == 1 {
-            load_game(state);
+            load_game(state)?;
         } else if state.current_tex == 2 {
             state.current_tex = 3;
         } else if state.current_tex == 3 {
             state.current_tex = 0;
         }
     }
+    Ok(())
 }
 
 // Shooting mechanics
@@ -348,7 +566,6 @@ fn handle_shot(state: &mut GameState) {
 #[allow(unused_must_use)]
 fn check_clear(state: &mut GameState) {
     if state.sprites[0].position.0 >= 2500 {
-        thread::sleep(time::Duration::from_millis(1000));
         if state.level != 3 {
             let save = state.level.to_string();
             fs::write("llama/save.txt", save);
@@ -359,31 +576,38 @@ fn check_clear(state: &mut GameState) {
     }
 }
 
-fn update_level(state: &mut GameState, input: &WinitInputHelper) {
+fn update_level(state: &mut GameState, input: &WinitInputHelper) -> Result<(), Error> {
     // TODO: SAVE GAME
     if input.key_pressed(VirtualKeyCode::Return) {
         state.next_level = false;
         state.level += 1;
         state.shots_left = 3;
-        if state.level == 2 {
-            state.sprites = level_2();
-        } else if state.level == 3 {
-            state.sprites = level_3();
-        }
-        for s in state.sprites.iter_mut() {
-            s.animation.set_state(0, state.frame)
+        if state.level == 2 || state.level == 3 {
+            load_level(state, state.level)?;
         }
     }
+    Ok(())
+}
+
+// Put the sprites and big asteroids for `level` in place, with everything on its first animation
+fn load_level(state: &mut GameState, level: u16) -> Result<(), Error> {
+    state.sprites = match level {
+        2 => level_2(&mut state.assets)?,
+        3 => level_3(&mut state.assets)?,
+        _ => level_1(&mut state.assets)?,
+    };
+    for s in state.sprites.iter_mut() {
+        s.animation.set_state(0, state.frame)
+    }
+    state.rocks = level_rocks(&mut state.assets, level)?;
+    Ok(())
 }
 
-fn load_game(state: &mut GameState) {
+fn load_game(state: &mut GameState) -> Result<(), Error> {
     if let Ok(level) = fs::read_to_string("llama/save.txt") {
         if level == "0" {
             state.level = 1;
-            state.sprites = level_1();
-            for s in state.sprites.iter_mut() {
-                s.animation.set_state(0, state.frame)
-            }
+            load_level(state, 1)?;
         } else if level == "1" {
             state.next_level = true;
             state.level = 1;
@@ -392,4 +616,5 @@ fn load_game(state: &mut GameState) {
             state.level = 2;
         }
     }
+    Ok(())
 }
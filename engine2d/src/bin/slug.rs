@@ -8,7 +8,6 @@ use winit::event::{Event, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
-use std::{thread, time};
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -19,13 +18,23 @@ use std::str::FromStr;
 
 
 
+use engine2d::assets::Assets;
 use engine2d::slug_maker::*;
 use engine2d::tile::*;
+use engine2d::offscreen::Offscreen;
+use engine2d::particles::Emitter;
+use engine2d::render_queue::*;
 use engine2d::screen::Screen;
+use engine2d::camera::Camera;
 use engine2d::collision::*;
+use engine2d::dirty::DirtyRects;
+use engine2d::error::Error;
+use engine2d::font::*;
 use engine2d::texture::Texture;
+use engine2d::transition::*;
 use engine2d::animation::*;
 use engine2d::sprite::*;
+use engine2d::terrain::Terrain;
 use engine2d::types::*;
 
 
@@ -35,9 +44,20 @@ struct GameState {
     textures: Vec<Rc<Texture>>,
     sprites: Vec<Sprite>,
     tilemap:Tilemap,
-    covered_tiles: usize,
+    // Mud the slug digs its way through, and the enemy can't
+    mud: Terrain,
     level: u16,
     current_tex: usize,
+    font: BitmapFont,
+    camera: Camera,
+    // Frames left before the game over screen, once the player's been caught
+    dying: usize,
+    // The level last drawn, so we notice when it changes
+    scene: u16,
+    transition: Option<Transition>,
+    slime: Emitter,
+    // Every texture we've loaded so far, so restarts don't decode them again
+    assets: Assets,
 }
 
 // seconds per frame
@@ -46,8 +66,28 @@ const DT: f64 = 1.0 / 60.0;
 const WIDTH: usize = 480;
 const HEIGHT: usize = 480;
 const DEPTH: usize = 4;
+// How many slimed tiles it takes to win
+const SLIME_TO_WIN: usize = 92;
 
 fn main() {
+    // `slug --screenshot out.png` renders the start of the game to a PNG without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "--screenshot" {
+        if let Err(e) = screenshot(Path::new(&args[2])) {
+            eprintln!("Couldn't take screenshot: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Load everything up front, so a missing asset is reported before a window opens
+    let mut state = match init() {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Couldn't load the game: {}", e);
+            eprintln!("(assets are loaded relative to the working directory; run from engine2d/)");
+            std::process::exit(1);
+        }
+    };
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
     let window = {
@@ -85,15 +125,6 @@ fn main() {
     //     }
     // }
 
-    let mut state = GameState {
-        // initial game state...
-        textures: make_menus(),
-        sprites: make_core(),
-        tilemap: reset_tiles(),
-        covered_tiles: 0,
-        level: 0,
-        current_tex: 0,
-    };
 
     // How many frames have we simulated?
     //60 FRAMES PER SECOND
@@ -104,60 +135,40 @@ fn main() {
     let start = Instant::now();
     // Track end of the last frame
     let mut since = Instant::now();
+    // Only the parts of the screen that changed get redrawn
+    let mut dirty = DirtyRects::new();
     event_loop.run(move |event, _, control_flow| {
         // Draw the current frame
-        let tile_map_position = Vec2i(1 as i32,1 as i32);
         if let Event::RedrawRequested(_) = event {
-            let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH, tile_map_position);
-            screen.clear(Rgba(0, 0, 0, 0));
-
+            let mut screen = Screen::wrap(pixels.get_frame(), WIDTH, HEIGHT, DEPTH, state.camera.offset());
             if state.level == 0 { // HOME SCREEN
-                update_menu(&mut state, &input);
-                screen.bitblt(
-                    &state.textures[state.current_tex],
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 480,
-                        h: 480,
-                    },
-                    Vec2i(0, 0),
-                );
-            } else if state.level == 2 { // GAME OVER
-                screen.bitblt(
-                    &state.textures[5],
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 480,
-                        h: 480,
-                    },
-                    Vec2i(0, 0),
-                );
-                if input.key_held(VirtualKeyCode::Return){
-                    state.level = 0;
+                if let Err(e) = update_menu(&mut state, &input) {
+                    eprintln!("Couldn't start the game: {}", e);
+                    *control_flow = ControlFlow::Exit;
+                    return;
                 }
-            } else if state.level == 3 { //WIN
-                screen.bitblt(
-                    &state.textures[4],
-                    Rect {
-                        x: 0,
-                        y: 0,
-                        w: 480,
-                        h: 480,
-                    },
-                    Vec2i(0, 0),
-                );
+            } else if state.level == 2 || state.level == 3 { // GAME OVER, WIN
                 if input.key_held(VirtualKeyCode::Return){
                     state.level = 0;
                 }
-
             } else {
-                draw_game(&mut state, &mut screen,frame_count);
+                for s in state.sprites.iter_mut() {
+                    s.animate(frame_count);
+                }
+            }
+            // Switching screens starts a transition away from the last frame, which is still on screen
+            // until the new one gets drawn
+            if state.level != state.scene {
+                state.scene = state.level;
+                state.transition = Some(transition_to(state.level));
             }
+            if let Some(transition) = &mut state.transition {
+                transition.capture(&screen);
+            }
+            let changed = dirty.draw(&mut screen, |screen| draw_frame(&state, screen));
 
-            // Flip buffers
-            if pixels.render().is_err() {
+            // Flip buffers, unless it'd show just the same picture again
+            if changed && pixels.render().is_err() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
@@ -176,12 +187,21 @@ fn main() {
             // Resize the window if needed
             if let Some(size) = input.window_resized() {
                 pixels.resize(size.width, size.height);
+                dirty.invalidate();
             }
         }
         // And the simulation "consumes" it
         while available_time >= DT {
             // Eat up one frame worth of time
             available_time -= DT;
+            // The game holds still while the screen changes
+            if let Some(transition) = &mut state.transition {
+                transition.update();
+                if transition.done() {
+                    state.transition = None;
+                }
+                continue;
+            }
             if state.level == 1{
                 update_game(&mut state, &input, frame_count);
                 // Increment the frame counter
@@ -195,23 +215,133 @@ fn main() {
     });
 }
 
-fn draw_game(state: &mut GameState, screen: &mut Screen,frame_number:usize) {
+// Everything the game needs before the first frame; fails if any asset can't be loaded
+fn init() -> Result<GameState, Error> {
+    let mut assets = Assets::new();
+    let tilemap = reset_tiles(&mut assets)?;
+    // The whole map fits on screen, so the camera only ever moves to shake
+    let mut camera = Camera::new((WIDTH as u32, HEIGHT as u32));
+    camera.bounds = Some(tilemap.bounds());
+    camera.reset(Vec2i(0, 0));
+    Ok(GameState {
+        // initial game state...
+        textures: make_menus(&mut assets)?,
+        sprites: make_core(&mut assets)?,
+        tilemap,
+        mud: make_mud(&mut assets)?,
+        level: 0,
+        current_tex: 0,
+        font: make_font(&mut assets)?,
+        camera,
+        dying: 0,
+        scene: 0,
+        transition: None,
+        slime: make_slime_trail(),
+        assets,
+    })
+}
+
+// Draw the first frame of a new game into an offscreen buffer and save it
+fn screenshot(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = init()?;
+    state.level = 1;
+    let mut offscreen = Offscreen::new(WIDTH, HEIGHT);
+    let mut screen = offscreen.screen(state.camera.offset());
+    for s in state.sprites.iter_mut() {
+        s.animate(0);
+    }
+    draw_game(&state, &mut screen);
+    offscreen.save_png(path)?;
+    Ok(())
+}
+
+// How to get to each screen: the game opens up out of an iris, winning fades in from white,
+// losing from black, and it's a quick wipe back to the menu
+fn transition_to(level: u16) -> Transition {
+    let black = Rgba(0, 0, 0, 255);
+    match level {
+        1 => Transition::new(Effect::Iris(black), 40),
+        2 => Transition::new(Effect::Fade(black), 40),
+        3 => Transition::new(Effect::Fade(Rgba(255, 255, 255, 255)), 60),
+        _ => Transition::new(Effect::Wipe, 30),
+    }
+}
+
+// Menus fill the whole window, whatever the camera is up to
+fn draw_menu(screen: &mut Screen, menu: &Texture) {
+    // They're fully opaque, so they can just be copied in
+    let opaque = DrawParams {
+        blend: BlendMode::Copy,
+        ..DrawParams::default()
+    };
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        w: 480,
+        h: 480,
+    };
+    screen.hud(|screen| screen.bitblt_with(menu, whole, Vec2i(0, 0), opaque));
+}
+
+// Draw whatever the game's showing right now from scratch, without changing anything
+fn draw_frame(state: &GameState, screen: &mut Screen) {
+    screen.clear(Rgba(0, 0, 0, 0));
+    if state.level == 0 { // HOME SCREEN
+        draw_menu(screen, &state.textures[state.current_tex]);
+    } else if state.level == 2 { // GAME OVER
+        draw_menu(screen, &state.textures[5]);
+    } else if state.level == 3 { //WIN
+        draw_menu(screen, &state.textures[4]);
+    } else {
+        draw_game(state, screen);
+    }
+    if let Some(transition) = &state.transition {
+        transition.draw(screen);
+    }
+}
+
+fn draw_game(state: &GameState, screen: &mut Screen) {
     // Call screen's drawing methods to render the game state
     screen.clear(Rgba(80, 80, 80, 255));
 
-    state.tilemap.draw(screen);
+    let covered = (all_slime(&state.tilemap) * 100 / SLIME_TO_WIN).min(100);
 
-    for s in state.sprites.iter_mut() {
-        // if frame_number%7 == 0 {
-        //     s.update_frame_pos();
-        // }
-        s.animate(frame_number);
-        screen.draw_sprite(s);
+    let mut queue = RenderQueue::new();
+    // The tiles are fully opaque, so they can just be copied in
+    let opaque = DrawParams {
+        blend: BlendMode::Copy,
+        ..DrawParams::default()
+    };
+    queue.tilemap(Layer::Background, 0, &state.tilemap, opaque);
+    queue.particles(Layer::Background, 1, &state.slime);
+    queue.terrain(Layer::Background, 2, &state.mud);
+    // Seen from above, whoever's lower down the screen is in front
+    for s in state.sprites.iter() {
+        queue.sprite(Layer::World, s.position.1, s);
     }
-
+    let slime = format!("SLIME {}%", covered);
+    let font = &state.font;
+    queue.custom(Layer::Hud, 0, move |screen| {
+        font.draw(screen, &slime, Vec2i(6, 6), TextStyle::default())
+    });
+    queue.draw(screen);
 }
 
 fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
+    // Once caught, everything freezes while the screen shakes, then it's game over
+    if state.dying > 0 {
+        state.dying -= 1;
+        if state.dying == 0 {
+            state.level = 2;
+            save_game(&mut state.tilemap);
+            state.camera.reset(Vec2i(0, 0));
+        }
+        state.slime.rate = 0.0;
+        state.slime.update();
+        state.camera.update();
+        return;
+    }
+    let start = state.sprites[0].position;
 
     //UPDATES RELATED TO THE PLAYER
     let x1 = state.sprites[0].position.0;
@@ -252,9 +382,8 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
     collided_tiles.insert(colliding_tile_br.0);
 
     if collided_tiles.contains(&3) {
-        thread::sleep(time::Duration::from_millis(500));
-        state.level = 2;
-        save_game(&mut state.tilemap);
+        die(state);
+        return;
     }
 
     //top left collides with a wall
@@ -294,25 +423,21 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
 
     if colliding_tile_tl.0 == 1  {
         let index = state.tilemap.tile_index_at(top_left);
-        state.covered_tiles += 1;
         state.tilemap.map[index] = TileID(2);
     }
 
     if colliding_tile_tr.0 == 1  {
         let index = state.tilemap.tile_index_at(top_right);
-        state.covered_tiles += 1;
         state.tilemap.map[index] = TileID(2);
     }
 
     if colliding_tile_bl.0 == 1  {
         let index = state.tilemap.tile_index_at(bottom_left);
-        state.covered_tiles += 1;
         state.tilemap.map[index] = TileID(2);
     }
 
     if colliding_tile_br.0 == 1  {
         let index = state.tilemap.tile_index_at(bottom_right);
-        state.covered_tiles += 1;
         state.tilemap.map[index] = TileID(2);
     }
 
@@ -322,12 +447,14 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
     // Player control goes here
     if input.key_held(VirtualKeyCode::Right) {
         state.sprites[0].position.0 += 2;
-        state.sprites[0].animation.set_state(2, frame);
+        state.sprites[0].animation.set_state(1, frame);
+        state.sprites[0].flip_x = true;
         shift_hitboxes(Vec2i(2,0), &mut state.sprites[0]);
     }
     if input.key_held(VirtualKeyCode::Left) {
         state.sprites[0].position.0 -= 2;
         state.sprites[0].animation.set_state(1, frame);
+        state.sprites[0].flip_x = false;
         shift_hitboxes(Vec2i(-2,0), &mut state.sprites[0]);
     }
 
@@ -355,8 +482,15 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
         state.sprites[0].animation.set_state(0, frame);
     }
 
+    // The slug eats its way through any mud it runs into
+    if terrain_contact(&state.sprites[0], &state.mud) {
+        let Vec2i(x, y) = state.sprites[0].position;
+        state.mud.carve_circle(Vec2i(x + 18, y + 24), 12);
+    }
+
     //ENEMY CONTROL
 
+    let enemy_start = state.sprites[1].position;
     let mut enemy_pos_x = state.sprites[1].position.0;
     let mut enemy_pos_y = state.sprites[1].position.1;
 
@@ -373,11 +507,13 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
 
     if x_distance > 0 {
             enemy_pos_x += 1;
-            state.sprites[1].animation.set_state(2, frame);
+            state.sprites[1].animation.set_state(1, frame);
+            state.sprites[1].flip_x = true;
             shift_hitboxes(Vec2i(1,0), &mut state.sprites[1]);
     } else if x_distance < 0 {
             enemy_pos_x -= 1;
             state.sprites[1].animation.set_state(1, frame);
+            state.sprites[1].flip_x = false;
             shift_hitboxes(Vec2i(-1,0), &mut state.sprites[1]);
     } else {
         state.sprites[1].animation.set_state(0, frame);
@@ -504,17 +640,31 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
     state.sprites[1].position.0 = enemy_pos_x;
     state.sprites[1].position.1 = enemy_pos_y;
 
-    if player_contacts(&state.sprites, 1) {
-        thread::sleep(time::Duration::from_millis(500));
-        state.level = 2;
-        save_game(&mut state.tilemap);
+    // The enemy can't dig, so mud stops it unless the slug's tunnelled through already
+    if terrain_contact(&state.sprites[1], &state.mud) {
+        let Vec2i(x, y) = enemy_start;
+        shift_hitboxes(Vec2i(x - enemy_pos_x, y - enemy_pos_y), &mut state.sprites[1]);
+        state.sprites[1].position = enemy_start;
+    }
 
+    if player_contacts(&state.sprites, 1) {
+        die(state);
+        return;
     }
 
-    if all_slime(&mut state.tilemap) >= 92 {
+    if all_slime(&state.tilemap) >= SLIME_TO_WIN {
         state.level = 3;
     }
 
+    // Slime oozes out from under the slug while it's on the move
+    let Vec2i(x, y) = state.sprites[0].position;
+    state.slime.position = Vec2i(x + 24, y + 40);
+    state.slime.rate = if state.sprites[0].position != start { 0.5 } else { 0.0 };
+    state.slime.update();
+
+    state.camera.follow(&state.sprites[0]);
+    state.camera.update();
+
 
 
     
@@ -525,7 +675,7 @@ fn update_game(state: &mut GameState, input: &WinitInputHelper, frame: usize) {
 
 
 // Function that takes care of moving around in the menu
-fn update_menu(state: &mut GameState, input: &WinitInputHelper) {
+fn update_menu(state: &mut GameState, input: &WinitInputHelper) -> Result<(), Error> {
     if input.key_pressed(VirtualKeyCode::Up) && state.current_tex != 3 {
         if state.current_tex != 0 {
             state.current_tex -= 1;
@@ -540,16 +690,18 @@ fn update_menu(state: &mut GameState, input: &WinitInputHelper) {
     }
     if input.key_pressed(VirtualKeyCode::Return) {
         if state.current_tex == 0 {
-            state.sprites = make_core();
-            state.covered_tiles = 0;
-            state.tilemap = reset_tiles();
+            state.sprites = make_core(&mut state.assets)?;
+            state.slime.clear();
+            state.tilemap = reset_tiles(&mut state.assets)?;
+            state.mud = make_mud(&mut state.assets)?;
             state.level = 1;
         } else if state.current_tex == 1 {
             //TODO: LOAD THE TILEMAP HERE AND SET THE STATE ACCORDINGLY
             // USE SOMETHING LIKE: load_game();
-            state.sprites = make_core();
-            state.covered_tiles = 0;
+            state.sprites = make_core(&mut state.assets)?;
+            state.slime.clear();
             load_game(&mut state.tilemap);
+            state.mud = make_mud(&mut state.assets)?;
             state.level = 1;
             
         } else if state.current_tex == 2 {
@@ -558,6 +710,12 @@ fn update_menu(state: &mut GameState, input: &WinitInputHelper) {
             state.current_tex = 0;
         }
     }
+    Ok(())
+}
+
+fn die(state: &mut GameState) {
+    state.camera.add_trauma(1.0);
+    state.dying = 30;
 }
 
 fn shift_hitboxes( Vec2i(x,y): Vec2i, sprite:  &mut Sprite){
@@ -595,9 +753,9 @@ fn load_game(tile_map : &mut Tilemap) {
 }
 
 //Determine player's winning conditions
-fn all_slime(tile_map : &mut Tilemap) -> usize {
+fn all_slime(tile_map : &Tilemap) -> usize {
     let mut total = 0;
-    for t in tile_map.map.iter_mut() {
+    for t in tile_map.map.iter() {
         if (t.0 == 2) {
             total = total + 1;
         }
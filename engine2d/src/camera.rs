@@ -0,0 +1,137 @@
+use crate::sprite::Sprite;
+use crate::types::{Rect, Vec2i};
+
+/// Decides which part of the world ends up on screen.
+/// Call `follow` and `update` once per simulation step and hand `offset()` to `Screen::wrap`.
+pub struct Camera {
+    // Top-left of the view in world coordinates, before any shake.
+    // It's fractional so that smoothing can creep up on a target.
+    x: f32,
+    y: f32,
+    /// Size of the view in pixels
+    pub size: (u32, u32),
+    /// The part of the view (relative to its top-left) the target can wander around in
+    /// before the camera starts following it
+    pub dead_zone: Rect,
+    /// How much of the way to the target the camera catches up each step; 1.0 sticks to it exactly
+    pub smoothing: f32,
+    /// World-space area the view has to stay inside, e.g. `Tilemap::bounds()`
+    pub bounds: Option<Rect>,
+    /// How far the view can be thrown, in pixels, at full trauma
+    pub max_shake: f32,
+    /// How much trauma wears off each step
+    pub recovery: f32,
+    trauma: f32,
+    time: f32,
+    shake: Vec2i,
+}
+
+impl Camera {
+    /// A camera at the origin that keeps its target dead center, doesn't shake and can go anywhere
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            size,
+            dead_zone: Rect {
+                x: size.0 as i32 / 2,
+                y: size.1 as i32 / 2,
+                w: 0,
+                h: 0,
+            },
+            smoothing: 1.0,
+            bounds: None,
+            max_shake: 8.0,
+            recovery: 1.0 / 60.0,
+            trauma: 0.0,
+            time: 0.0,
+            shake: Vec2i(0, 0),
+        }
+    }
+
+    /// Where the view is, ignoring shake
+    pub fn position(&self) -> Vec2i {
+        Vec2i(self.x.round() as i32, self.y.round() as i32)
+    }
+
+    /// Where the view is, shake included; this is what `Screen` wants
+    pub fn offset(&self) -> Vec2i {
+        let Vec2i(x, y) = self.position();
+        Vec2i(x + self.shake.0, y + self.shake.1)
+    }
+
+    /// Jump straight to `position` (still inside the bounds) and stop shaking, e.g. when a level starts
+    pub fn reset(&mut self, Vec2i(x, y): Vec2i) {
+        self.x = x as f32;
+        self.y = y as f32;
+        self.trauma = 0.0;
+        self.shake = Vec2i(0, 0);
+        self.clamp();
+    }
+
+    /// Move towards keeping the middle of `target`'s current frame inside the dead zone
+    pub fn follow(&mut self, target: &Sprite) {
+        let state = &target.animation.states[target.animation.index];
+        let frame = state.frames[state.current_index];
+        let Vec2i(x, y) = target.position;
+        self.follow_point(Vec2i(x + frame.w as i32 / 2, y + frame.h as i32 / 2));
+    }
+
+    /// Move towards keeping the world-space point `target` inside the dead zone
+    pub fn follow_point(&mut self, Vec2i(tx, ty): Vec2i) {
+        let dz = self.dead_zone;
+        let goal = |pos: f32, target: i32, lo: i32, len: u16| {
+            let target = target as f32;
+            if target < pos + lo as f32 {
+                target - lo as f32
+            } else if target > pos + (lo + len as i32) as f32 {
+                target - (lo + len as i32) as f32
+            } else {
+                pos
+            }
+        };
+        let (gx, gy) = (goal(self.x, tx, dz.x, dz.w), goal(self.y, ty, dz.y, dz.h));
+        self.x += (gx - self.x) * self.smoothing;
+        self.y += (gy - self.y) * self.smoothing;
+        self.clamp();
+    }
+
+    /// Shake things up; trauma is capped at 1.0 and the shake grows with its square
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Advance the shake by one step and let some trauma wear off
+    pub fn update(&mut self) {
+        self.time += 1.0;
+        let amount = self.max_shake * self.trauma * self.trauma;
+        // Two out-of-step sine waves make a cheap, smooth, repeatable noise
+        let wobble =
+            |t: f32, seed: f32| ((t * 0.9 + seed).sin() + 0.6 * (t * 2.3 + seed * 1.7).sin()) / 1.6;
+        self.shake = Vec2i(
+            (amount * wobble(self.time, 0.0)).round() as i32,
+            (amount * wobble(self.time, 5.3)).round() as i32,
+        );
+        self.trauma = (self.trauma - self.recovery).max(0.0);
+    }
+
+    // Keep the view inside the bounds, or centered on them if they're smaller than the view
+    fn clamp(&mut self) {
+        if let Some(b) = self.bounds {
+            let fit = |pos: f32, lo: i32, len: u16, view: u32| {
+                let (lo, len, view) = (lo as f32, len as f32, view as f32);
+                if len <= view {
+                    lo + (len - view) / 2.0
+                } else {
+                    pos.max(lo).min(lo + len - view)
+                }
+            };
+            self.x = fit(self.x, b.x, b.w, self.size.0);
+            self.y = fit(self.y, b.y, b.h, self.size.1);
+        }
+    }
+}
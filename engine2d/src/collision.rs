@@ -1,6 +1,7 @@
 use crate::animation::StartAnim;
 use crate::sprite::Sprite;
-use crate::types::Rect;
+use crate::terrain::Terrain;
+use crate::types::{Rect, Vec2i};
 
 pub fn player_contacts(sprites: &Vec<Sprite>, start_index: usize) -> bool {
     // collide player against objects
@@ -22,7 +23,8 @@ pub fn player_contacts(sprites: &Vec<Sprite>, start_index: usize) -> bool {
     return false;
 }
 
-pub fn laser_contacts(sprites: &mut Vec<Sprite>, frame: usize) {
+// Returns where each sprite that just blew up is
+pub fn laser_contacts(sprites: &mut Vec<Sprite>, frame: usize) -> Vec<Vec2i> {
     let mut contacts: Vec<(usize, usize)> = vec![];
     for laser in 1..4 {
         for i in 4..sprites.len() {
@@ -35,16 +37,26 @@ pub fn laser_contacts(sprites: &mut Vec<Sprite>, frame: usize) {
             }
         }
     }
+    let mut exploded = vec![];
     for (laser, i) in contacts {
         sprites[laser].position.1 = -200;
         sprites[laser].hit_boxes[0].y = -20;
         sprites[laser].hit_boxes[0].x = -20;
         if sprites[i].is_explodable {
+            if !sprites[i].exploded {
+                exploded.push(sprites[i].position);
+            }
             sprites[i].exploded = true;
             sprites[i].is_obstacle = false;
             sprites[i].animation.set_state(1, frame);
         }
     }
+    exploded
+}
+
+// Whether any of the sprite's hit boxes runs into something solid in the terrain
+pub fn terrain_contact(sprite: &Sprite, terrain: &Terrain) -> bool {
+    sprite.hit_boxes.iter().any(|hit_box| terrain.overlaps(*hit_box))
 }
 
 pub fn rect_displacement(r1: Rect, r2: Rect) -> bool {
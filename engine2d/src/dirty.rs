@@ -0,0 +1,159 @@
+use crate::screen::Screen;
+use crate::types::*;
+use std::collections::HashMap;
+
+/// One draw call, as far as `DirtyRects` is concerned
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Draw {
+    /// The part of the screen it could have touched, in screen coordinates
+    pub area: Rect,
+    /// A hash of everything that decides what it looks like
+    pub key: u64,
+}
+
+// Past this many separate rects, redraw one big one covering them all instead
+const MAX_RECTS: usize = 8;
+
+/// Remembers what was drawn last frame, so the next frame only has to redraw the parts of the screen that changed.
+/// Hand `draw` a closure that draws the whole frame, starting with a `clear`, and doesn't change anything else:
+/// it gets run once with nothing actually drawn to see what the frame is made of, then once more clipped to
+/// each part that's different. Everything gets redrawn if the camera moves.
+/// The framebuffer has to still have last frame's picture in it, like the one from `Pixels::get_frame` does.
+#[derive(Default)]
+pub struct DirtyRects {
+    // Last frame's camera, screen size and draw calls, unless the next frame should be drawn from scratch
+    last: Option<(Vec2i, Rect, Vec<Draw>)>,
+    marked: Vec<Rect>,
+    redrawn: Vec<Rect>,
+}
+
+impl DirtyRects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redraw everything next frame, e.g. after the framebuffer's been drawn over some other way
+    pub fn invalidate(&mut self) {
+        self.last = None;
+    }
+
+    /// Redraw `area` (in screen coordinates) next frame even if nothing drawn there seems to have changed,
+    /// e.g. because a texture drawn there was changed in place
+    pub fn mark(&mut self, area: Rect) {
+        self.marked.push(area);
+    }
+
+    /// The parts of the screen the last `draw` redrew, in screen coordinates
+    pub fn redrawn(&self) -> &[Rect] {
+        &self.redrawn
+    }
+
+    /// Bring the screen up to date with what `draw` draws, only redrawing what's changed since last time.
+    /// Returns whether anything was redrawn; if not, the picture's the same and there's no need to show it again.
+    pub fn draw(&mut self, screen: &mut Screen, mut draw: impl FnMut(&mut Screen)) -> bool {
+        let camera = screen.position;
+        let whole = Rect {
+            x: 0,
+            y: 0,
+            ..screen.bounds()
+        };
+        let clip = screen.clip();
+        // Find out what the frame is made of without drawing any of it
+        screen.set_logging(true);
+        screen.set_clip(Rect { w: 0, h: 0, ..clip });
+        draw(screen);
+        let log = screen.take_log();
+        let dirty = match self.last.take() {
+            Some((last_camera, last_size, last_log))
+                if last_camera == camera && last_size == whole =>
+            {
+                let mut dirty = changes(&last_log, &log);
+                dirty.append(&mut self.marked);
+                dirty
+            }
+            _ => vec![whole],
+        };
+        self.marked.clear();
+        self.redrawn = merge(dirty.iter().map(|area| area.intersection(clip)).collect());
+        for &area in self.redrawn.iter() {
+            screen.set_clip(area);
+            draw(screen);
+        }
+        screen.set_clip(clip);
+        self.last = Some((camera, whole, log));
+        !self.redrawn.is_empty()
+    }
+}
+
+// Where the draws in `new` could leave a different picture than the ones in `old` did
+fn changes(old: &[Draw], new: &[Draw]) -> Vec<Rect> {
+    // Only the stretch between what's the same at the start and the end matters
+    let same = |(a, b): &(&Draw, &Draw)| a == b;
+    let start = old.iter().zip(new).take_while(same).count();
+    let (old, new) = (&old[start..], &new[start..]);
+    let end = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(same)
+        .count();
+    let (old, new) = (&old[..old.len() - end], &new[..new.len() - end]);
+    // Pair up draws both frames have; the ones left over are where things changed
+    let mut counts: HashMap<Draw, (usize, usize)> = HashMap::new();
+    for d in old {
+        counts.entry(*d).or_default().0 += 1;
+    }
+    for d in new {
+        counts.entry(*d).or_default().1 += 1;
+    }
+    let mut dirty = vec![];
+    let mut paired = |draws: &[Draw]| {
+        let mut left: HashMap<Draw, usize> =
+            counts.iter().map(|(d, &(o, n))| (*d, o.min(n))).collect();
+        let mut kept = vec![];
+        for d in draws {
+            match left.get_mut(d) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    kept.push(*d);
+                }
+                _ => dirty.push(d.area),
+            }
+        }
+        kept
+    };
+    let (kept_old, kept_new) = (paired(old), paired(new));
+    // If the ones they share got shuffled around, anything in between could look different
+    if kept_old != kept_new {
+        dirty.extend(kept_old.iter().chain(kept_new.iter()).map(|d| d.area));
+    }
+    dirty
+}
+
+// Fold together rects that overlap, so nothing gets redrawn twice
+fn merge(areas: Vec<Rect>) -> Vec<Rect> {
+    let mut merged: Vec<Rect> = vec![];
+    for mut area in areas.into_iter().filter(|a| !a.is_empty()) {
+        // Growing can make it overlap rects it didn't before, so keep going until it stops
+        loop {
+            let before = merged.len();
+            merged.retain(|m| {
+                if m.intersection(area).is_empty() {
+                    true
+                } else {
+                    area = area.union(*m);
+                    false
+                }
+            });
+            if merged.len() == before {
+                break;
+            }
+        }
+        merged.push(area);
+    }
+    if merged.len() > MAX_RECTS {
+        let all = merged.iter().fold(merged[0], |all, m| all.union(*m));
+        merged = vec![all];
+    }
+    merged
+}
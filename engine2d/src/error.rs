@@ -0,0 +1,98 @@
+use crate::font::FontError;
+use crate::types::Rect;
+use image::ImageError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Anything that can go wrong while loading game assets
+#[derive(Debug)]
+pub enum Error {
+    /// An image file was missing, unreadable or not an image we can decode
+    Image { path: PathBuf, source: ImageError },
+    /// Image data in memory couldn't be decoded
+    Decode(ImageError),
+    /// Some other file (font metrics, say) couldn't be read
+    Io { path: PathBuf, source: io::Error },
+    /// A font metrics file was read but didn't make sense
+    Font { path: PathBuf, source: FontError },
+    /// A TrueType/OpenType font file was read but the rasterizer didn't understand it
+    TrueType {
+        path: PathBuf,
+        message: &'static str,
+    },
+    /// TrueType/OpenType font data in memory couldn't be parsed
+    FontData(&'static str),
+    /// An atlas manifest line (1-based) didn't make sense
+    Manifest { line: usize, message: String },
+    /// Frame `frame` of animation state `state` isn't entirely inside its `size`d texture
+    BadFrame {
+        state: usize,
+        frame: usize,
+        rect: Rect,
+        size: (usize, usize),
+    },
+    /// Animation state `state` has no frames to show
+    NoFrames { state: usize },
+    /// Tile `tile` of a tileset isn't entirely inside its `size`d texture
+    BadTile {
+        tile: usize,
+        rect: Rect,
+        size: (usize, usize),
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Image { path, source } => {
+                write!(f, "couldn't load image {}: {}", path.display(), source)
+            }
+            Error::Decode(source) => write!(f, "couldn't decode image: {}", source),
+            Error::Io { path, source } => write!(f, "couldn't read {}: {}", path.display(), source),
+            Error::Font { path, source } => write!(f, "bad font {}: {}", path.display(), source),
+            Error::TrueType { path, message } => {
+                write!(f, "couldn't parse font {}: {}", path.display(), message)
+            }
+            Error::FontData(message) => write!(f, "couldn't parse font data: {}", message),
+            Error::Manifest { line, message } => {
+                write!(f, "atlas manifest line {}: {}", line, message)
+            }
+            Error::BadFrame {
+                state,
+                frame,
+                rect,
+                size,
+            } => write!(
+                f,
+                "animation state {} frame {} ({:?}) runs outside the {}x{} texture",
+                state, frame, rect, size.0, size.1
+            ),
+            Error::NoFrames { state } => write!(f, "animation state {} has no frames", state),
+            Error::BadTile { tile, rect, size } => write!(
+                f,
+                "tile {} ({:?}) runs outside the {}x{} texture",
+                tile, rect, size.0, size.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Image { source, .. } => Some(source),
+            Error::Decode(source) => Some(source),
+            Error::Io { source, .. } => Some(source),
+            Error::Font { source, .. } => Some(source),
+            Error::TrueType { .. }
+            | Error::FontData(_)
+            | Error::Manifest { .. }
+            | Error::BadFrame { .. }
+            | Error::NoFrames { .. }
+            | Error::BadTile { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::assets::Assets;
+use crate::error::{self, Error};
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::{DrawParams, Rect, Rgba, Vec2i};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Where one character lives in the atlas and how to place it
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Glyph {
+    /// The glyph's pixels in the font texture
+    pub rect: Rect,
+    /// Where to draw `rect` relative to the pen position (top of the line)
+    pub offset: Vec2i,
+    /// How far to move the pen afterwards
+    pub advance: i32,
+}
+
+/// How each line of text sits relative to the position it's drawn at
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Everything about how a string gets laid out and drawn
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TextStyle {
+    /// Multiplied into the glyphs, so white glyphs come out in exactly this color
+    pub color: Rgba,
+    pub align: Align,
+    /// Break lines between words so none is wider than this many pixels.
+    /// With wrapping, alignment is within a box this wide starting at the draw position;
+    /// without it, alignment is around the draw position.
+    pub wrap: Option<u32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: Rgba(255, 255, 255, 255),
+            align: Align::Left,
+            wrap: None,
+        }
+    }
+}
+
+/// A problem with a font's metrics description
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FontError {
+    /// 1-based line of the description where things went wrong
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "font metrics line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// A font made of pre-drawn glyphs packed into one texture
+pub struct BitmapFont {
+    pub texture: Rc<Texture>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    /// Distance from one line's top to the next
+    pub line_height: i32,
+}
+
+impl BitmapFont {
+    /// Make a font out of an atlas and its glyphs directly
+    pub fn new(
+        texture: &Rc<Texture>,
+        glyphs: HashMap<char, Glyph>,
+        kerning: HashMap<(char, char), i32>,
+        line_height: i32,
+    ) -> Self {
+        Self {
+            texture: Rc::clone(texture),
+            glyphs,
+            kerning,
+            line_height,
+        }
+    }
+
+    /// Read the metrics from an AngelCode BMFont text description (a `.fnt` file).
+    /// We use the `common`, `char` and `kerning` lines and ignore the rest;
+    /// the font must fit on the one page that is `texture`.
+    pub fn from_bmfont(texture: &Rc<Texture>, metrics: &str) -> Result<Self, FontError> {
+        let mut glyphs = HashMap::new();
+        let mut kerning = HashMap::new();
+        let mut line_height = None;
+        for (i, line) in metrics.lines().enumerate() {
+            let err = |message: String| FontError {
+                line: i + 1,
+                message,
+            };
+            let mut words = line.split_whitespace();
+            let tag = match words.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let mut fields = HashMap::new();
+            for word in words {
+                if let Some(eq) = word.find('=') {
+                    fields.insert(&word[..eq], &word[eq + 1..]);
+                }
+            }
+            let num = |key: &str| -> Result<i32, FontError> {
+                let val = fields
+                    .get(key)
+                    .ok_or_else(|| err(format!("missing {}", key)))?;
+                val.parse()
+                    .map_err(|_| err(format!("bad number {}={}", key, val)))
+            };
+            let id = |key: &str| -> Result<char, FontError> {
+                let n = num(key)?;
+                std::char::from_u32(n as u32)
+                    .ok_or_else(|| err(format!("{} is not a character", n)))
+            };
+            match tag {
+                "common" => line_height = Some(num("lineHeight")?),
+                "char" => {
+                    let (w, h) = (num("width")?, num("height")?);
+                    if w < 0 || h < 0 {
+                        return Err(err(format!("negative glyph size {}x{}", w, h)));
+                    }
+                    if w > u16::MAX as i32 || h > u16::MAX as i32 {
+                        return Err(err(format!("glyph size {}x{} is too big", w, h)));
+                    }
+                    let rect = Rect {
+                        x: num("x")?,
+                        y: num("y")?,
+                        w: w as u16,
+                        h: h as u16,
+                    };
+                    // Caught now rather than the first time the character gets drawn
+                    if !texture.valid_frame(rect) {
+                        let (tw, th) = texture.size();
+                        return Err(err(format!(
+                            "glyph {:?} runs outside the {}x{} texture",
+                            rect, tw, th
+                        )));
+                    }
+                    let glyph = Glyph {
+                        rect,
+                        offset: Vec2i(num("xoffset")?, num("yoffset")?),
+                        advance: num("xadvance")?,
+                    };
+                    glyphs.insert(id("id")?, glyph);
+                }
+                "kerning" => {
+                    kerning.insert((id("first")?, id("second")?), num("amount")?);
+                }
+                _ => {}
+            }
+        }
+        let line_height = line_height.ok_or(FontError {
+            line: 0,
+            message: "no common line with a lineHeight".to_string(),
+        })?;
+        Ok(Self::new(texture, glyphs, kerning, line_height))
+    }
+
+    /// Load a font from its atlas image (through `assets`, so it's shared) and its `.fnt` metrics file
+    pub fn load(assets: &mut Assets, texture: &Path, metrics: &Path) -> error::Result<Self> {
+        let texture = assets.texture(texture)?;
+        let text = fs::read_to_string(metrics).map_err(|source| Error::Io {
+            path: metrics.to_path_buf(),
+            source,
+        })?;
+        Self::from_bmfont(&texture, &text).map_err(|source| Error::Font {
+            path: metrics.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The glyph we draw for `c`: its own, its uppercase version, or failing those a '?'
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()))
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Extra space (usually negative) between this pair of characters
+    pub fn kern(&self, first: char, second: char) -> i32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    /// How wide a single line of text is
+    pub fn line_width(&self, line: &str) -> i32 {
+        let mut width = 0;
+        let mut prev = None;
+        for c in line.chars() {
+            if let Some(p) = prev {
+                width += self.kern(p, c);
+            }
+            width += self.glyph(c).map_or(0, |g| g.advance);
+            prev = Some(c);
+        }
+        width
+    }
+
+    /// Break text into lines at newlines and, if `wrap` is given, between words.
+    /// A single word wider than `wrap` gets a line to itself rather than being split.
+    pub fn layout<'t>(&self, text: &'t str, wrap: Option<u32>) -> Vec<&'t str> {
+        let mut lines = vec![];
+        for paragraph in text.split('\n') {
+            let max = match wrap {
+                Some(max) => max as i32,
+                None => {
+                    lines.push(paragraph);
+                    continue;
+                }
+            };
+            // Byte ranges of each word within the paragraph
+            let words = paragraph.split_whitespace().map(|w| {
+                let start = w.as_ptr() as usize - paragraph.as_ptr() as usize;
+                (start, start + w.len())
+            });
+            let mut line: Option<(usize, usize)> = None;
+            for (start, end) in words {
+                line = match line {
+                    Some((s, e)) if self.line_width(&paragraph[s..end]) > max => {
+                        lines.push(&paragraph[s..e]);
+                        Some((start, end))
+                    }
+                    Some((s, _)) => Some((s, end)),
+                    None => Some((start, end)),
+                };
+            }
+            lines.push(line.map_or("", |(s, e)| &paragraph[s..e]));
+        }
+        lines
+    }
+
+    /// Width and height of the block `text` would take up
+    pub fn measure(&self, text: &str, wrap: Option<u32>) -> (i32, i32) {
+        let lines = self.layout(text, wrap);
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line))
+            .max()
+            .unwrap_or(0);
+        (width, lines.len() as i32 * self.line_height)
+    }
+
+    /// Draw `text` with the top of its first line at `position` (in world coordinates, like everything else)
+    pub fn draw(&self, screen: &mut Screen, text: &str, position: Vec2i, style: TextStyle) {
+        let params = DrawParams {
+            tint: style.color,
+            ..DrawParams::default()
+        };
+        let mut y = position.1;
+        for line in self.layout(text, style.wrap) {
+            let width = self.line_width(line);
+            let mut x = match (style.align, style.wrap) {
+                (Align::Left, _) => position.0,
+                (Align::Center, Some(max)) => position.0 + (max as i32 - width) / 2,
+                (Align::Right, Some(max)) => position.0 + max as i32 - width,
+                (Align::Center, None) => position.0 - width / 2,
+                (Align::Right, None) => position.0 - width,
+            };
+            let mut prev = None;
+            for c in line.chars() {
+                if let Some(p) = prev {
+                    x += self.kern(p, c);
+                }
+                if let Some(g) = self.glyph(c) {
+                    if g.rect.w > 0 && g.rect.h > 0 {
+                        let to = Vec2i(x + g.offset.0, y + g.offset.1);
+                        screen.bitblt_with(&self.texture, g.rect, to, params);
+                    }
+                    x += g.advance;
+                }
+                prev = Some(c);
+            }
+            y += self.line_height;
+        }
+    }
+}
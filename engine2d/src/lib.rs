@@ -1,9 +1,24 @@
 pub mod animation;
+pub mod assets;
+pub mod atlas;
+pub mod batch;
+pub mod camera;
 pub mod collision;
+pub mod dirty;
+pub mod error;
+pub mod font;
 pub mod llama_maker;
+pub mod offscreen;
+pub mod parallax;
+pub mod particles;
+pub mod recolor;
+pub mod render_queue;
 pub mod slug_maker;
 pub mod screen;
 pub mod sprite;
+pub mod terrain;
 pub mod texture;
 pub mod tile;
+pub mod transition;
+pub mod truetype;
 pub mod types;
\ No newline at end of file
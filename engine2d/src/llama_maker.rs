@@ -1,13 +1,22 @@
 use crate::animation::Animation;
+use crate::assets::Assets;
+use crate::error::Result;
+use crate::font::BitmapFont;
+use crate::parallax::{Backdrop, Parallax};
+use crate::particles::{Emitter, Shape};
 use crate::sprite::Sprite;
+use crate::terrain::Terrain;
 use crate::texture::Texture;
+use crate::tile::{Tile, Tilemap, Tileset};
 use crate::types::*;
+use image::RgbaImage;
 use rand::Rng;
+use std::ops::Range;
 use std::path::Path;
 use std::rc::Rc;
 
-pub fn make_asteroid(position: Vec2i) -> Sprite {
-    let asteroid = Rc::new(Texture::with_file(Path::new("llama/sprites/asteroid.png")));
+pub fn make_asteroid(assets: &mut Assets, position: Vec2i) -> Result<Sprite> {
+    let asteroid = assets.texture(Path::new("llama/sprites/asteroid.png"))?;
     Sprite::new(
         &asteroid,
         Animation::new(vec![AnimationState {
@@ -42,8 +51,8 @@ pub fn make_asteroid(position: Vec2i) -> Sprite {
     )
 }
 
-pub fn make_break_asteroid(position: Vec2i) -> Sprite {
-    let break_asteroid = Rc::new(Texture::with_file(Path::new("llama/sprites/b_asteroid.png")));
+pub fn make_break_asteroid(assets: &mut Assets, position: Vec2i) -> Result<Sprite> {
+    let break_asteroid = assets.texture(Path::new("llama/sprites/b_asteroid.png"))?;
     Sprite::new(
         &break_asteroid,
         Animation::new(vec![
@@ -95,8 +104,8 @@ pub fn make_break_asteroid(position: Vec2i) -> Sprite {
     )
 }
 
-pub fn make_little_asteroid(position: Vec2i) -> Sprite {
-    let asteroid = Rc::new(Texture::with_file(Path::new("llama/sprites/l_asteroid.png")));
+pub fn make_little_asteroid(assets: &mut Assets, position: Vec2i) -> Result<Sprite> {
+    let asteroid = assets.texture(Path::new("llama/sprites/l_asteroid.png"))?;
     Sprite::new(
         &asteroid,
         Animation::new(vec![AnimationState {
@@ -123,9 +132,9 @@ pub fn make_little_asteroid(position: Vec2i) -> Sprite {
     )
 }
 
-pub fn make_laser() -> Sprite {
-    let laser = Rc::new(Texture::with_file(Path::new("llama/sprites/laser.png")));
-    Sprite::new(
+pub fn make_laser(assets: &mut Assets) -> Result<Sprite> {
+    let laser = assets.texture(Path::new("llama/sprites/laser.png"))?;
+    let mut sprite = Sprite::new(
         // Laser 1
         &laser,
         Animation::new(vec![AnimationState {
@@ -149,11 +158,14 @@ pub fn make_laser() -> Sprite {
         false,
         false,
         false,
-    )
+    )?;
+    // Lasers glow over whatever they pass
+    sprite.blend = BlendMode::Additive;
+    Ok(sprite)
 }
 
-pub fn make_ship() -> Sprite {
-    let ship = Rc::new(Texture::with_file(Path::new("llama/sprites/ship.png")));
+pub fn make_ship(assets: &mut Assets) -> Result<Sprite> {
+    let ship = assets.texture(Path::new("llama/sprites/ship.png"))?;
     Sprite::new(
         &ship,
         Animation::new(vec![AnimationState {
@@ -175,8 +187,8 @@ pub fn make_ship() -> Sprite {
     )
 }
 
-pub fn make_player() -> Sprite {
-    let llama = Rc::new(Texture::with_file(Path::new("llama/sprites/llama.png")));
+pub fn make_player(assets: &mut Assets) -> Result<Sprite> {
+    let llama = assets.texture(Path::new("llama/sprites/llama.png"))?;
     Sprite::new(
         &llama,
         Animation::new(vec![
@@ -264,9 +276,9 @@ pub fn make_player() -> Sprite {
     )
 }
 
-pub fn make_battery() -> Sprite {
-    let battery = Rc::new(Texture::with_file(Path::new("llama/sprites/battery.png")));
-    Sprite::new(
+pub fn make_battery(assets: &mut Assets) -> Result<Sprite> {
+    let battery = assets.texture(Path::new("llama/sprites/battery.png"))?;
+    let mut sprite = Sprite::new(
         &battery,
         Animation::new(vec![
             AnimationState {
@@ -319,45 +331,179 @@ pub fn make_battery() -> Sprite {
         false,
         false,
         false,
-    )
+    )?;
+    // The battery is part of the HUD: it stays in the top right corner whatever the camera does
+    sprite.hud = true;
+    Ok(sprite)
+}
+
+/// Fire and flying rock for when a breakable asteroid is shot: move each emitter to the middle
+/// of the asteroid and `burst` it
+pub fn make_explosion(assets: &mut Assets) -> Result<Vec<Emitter>> {
+    let rock = assets.texture(Path::new("llama/sprites/b_asteroid.png"))?;
+    let mut fire = Emitter::new(Vec2i(0, 0), Shape::Circle);
+    fire.radius = 6.0;
+    fire.speed = 0.5..2.5;
+    fire.drag = 0.92;
+    fire.lifetime = 15..30;
+    fire.color = (Rgba(255, 220, 90, 255), Rgba(200, 40, 20, 0));
+    fire.size = (7.0, 2.0);
+    // Chips off the middle of the unbroken asteroid
+    let chip = Rect {
+        x: 12,
+        y: 12,
+        w: 6,
+        h: 6,
+    };
+    let mut debris = Emitter::new(Vec2i(0, 0), Shape::Frame(rock, chip));
+    debris.speed = 1.0..3.0;
+    debris.gravity = 0.05;
+    debris.lifetime = 30..50;
+    debris.color = (Rgba(255, 255, 255, 255), Rgba(255, 255, 255, 0));
+    debris.size = (6.0, 3.0);
+    Ok(vec![fire, debris])
+}
+
+/// A big asteroid, twice the size of the others, that lasers chip away at bit by bit
+pub fn make_rock(assets: &mut Assets, position: Vec2i) -> Result<Terrain> {
+    let asteroid = assets.texture(Path::new("llama/sprites/asteroid.png"))?;
+    let frame = Rect {
+        x: 0,
+        y: 0,
+        w: 32,
+        h: 32,
+    };
+    let double = Transform {
+        rotation: 0.0,
+        scale: (2.0, 2.0),
+        pivot: (0.0, 0.0),
+    };
+    let mut texture = Texture::blank(64, 64);
+    texture.canvas(Vec2i(0, 0)).bitblt_transformed(
+        &asteroid,
+        frame,
+        Vec2i(0, 0),
+        double,
+        DrawParams::default(),
+    );
+    Ok(Terrain::new(texture, position))
+}
+
+/// The big asteroids in each level, in the gaps between the little ones
+pub fn level_rocks(assets: &mut Assets, level: u16) -> Result<Vec<Terrain>> {
+    let positions = match level {
+        1 => vec![Vec2i(1100, 60), Vec2i(1950, 120)],
+        2 => vec![Vec2i(1100, 110), Vec2i(1600, 20)],
+        3 => vec![Vec2i(800, 70), Vec2i(1600, 100), Vec2i(2300, 40)],
+        _ => vec![],
+    };
+    positions
+        .into_iter()
+        .map(|position| make_rock(assets, position))
+        .collect()
+}
+
+/// Puffs out of the llama's jetpack; keep it under the jetpack and turn `rate` up while thrusting
+pub fn make_exhaust() -> Emitter {
+    let mut exhaust = Emitter::new(Vec2i(0, 0), Shape::Square);
+    exhaust.radius = 2.0;
+    // Mostly straight down
+    exhaust.angle = 1.3..1.85;
+    exhaust.speed = 1.0..2.0;
+    exhaust.lifetime = 10..20;
+    exhaust.color = (Rgba(255, 200, 60, 255), Rgba(120, 120, 120, 0));
+    exhaust.size = (3.0, 1.0);
+    exhaust
 }
 
-pub fn make_core() -> Vec<Sprite> {
+pub fn make_core(assets: &mut Assets) -> Result<Vec<Sprite>> {
     let mut core: Vec<Sprite> = vec![];
-    core.push(make_player());
+    core.push(make_player(assets)?);
     for _ in 0..3 {
-        core.push(make_laser());
+        core.push(make_laser(assets)?);
     }
-    core.push(make_battery());
-    core.push(make_ship());
-    return core;
+    core.push(make_battery(assets)?);
+    core.push(make_ship(assets)?);
+    return Ok(core);
 }
 
-pub fn make_map() -> Vec<usize> {
-    // 64 * 8 dims
-    // 7 rows are sky, 1 row is floor
+// A `w` by `h` map of tiles picked at random from `tiles`
+fn make_map(w: usize, h: usize, tiles: Range<usize>) -> Vec<usize> {
     let mut rng = rand::thread_rng();
-    let mut sky: Vec<usize> = (0..256).map(|_| (rng.gen_range(0, 4) as usize)).collect();
-    let mut floor: Vec<usize> = (0..128).map(|_| (rng.gen_range(4, 8) as usize)).collect();
-    sky.append(&mut floor);
-    sky
+    (0..w * h)
+        .map(|_| rng.gen_range(tiles.start, tiles.end))
+        .collect()
+}
+
+// A transparent texture sprinkled with `count` faint single-pixel stars
+fn make_stars(w: u32, h: u32, count: usize) -> Texture {
+    let mut rng = rand::thread_rng();
+    let mut image = RgbaImage::new(w, h);
+    for _ in 0..count {
+        let (x, y) = (rng.gen_range(0, w), rng.gen_range(0, h));
+        image.put_pixel(x, y, image::Rgba([255, 255, 255, rng.gen_range(80, 200)]));
+    }
+    Texture::new(image)
+}
+
+// The sky and the ground never change, so they can be drawn in one go rather than tile by tile
+fn baked(map: &Tilemap) -> Backdrop {
+    Backdrop::Texture {
+        texture: Rc::new(map.bake()),
+        position: map.position,
+    }
+}
+
+/// Everything behind the sprites, back to front: the sky creeps by, a sprinkling of nearer stars
+/// drifts past a bit faster and the ground, 64 tiles long, moves along with the level
+pub fn make_backdrop(assets: &mut Assets) -> Result<Vec<Parallax>> {
+    // The top row of the tileset is sky, the bottom row is ground
+    let tex = assets.texture(Path::new("llama/space_tileset.png"))?;
+    let tileset = Rc::new(Tileset::new(vec![Tile { solid: false }; 8], &tex)?);
+    // The tiles are fully opaque, so they can just be copied in
+    let opaque = DrawParams {
+        blend: BlendMode::Copy,
+        ..DrawParams::default()
+    };
+    let sky = Tilemap::new(Vec2i(0, 0), (5, 5), &tileset, make_map(5, 5, 0..4));
+    let mut sky = Parallax::new(baked(&sky), (0.2, 0.2));
+    sky.wrap = (true, true);
+    sky.params = opaque;
+    let mut stars = Parallax::new(
+        Backdrop::Texture {
+            texture: Rc::new(make_stars(240, 240, 40)),
+            position: Vec2i(0, 0),
+        },
+        (0.5, 0.5),
+    );
+    stars.wrap = (true, true);
+    let ground = Tilemap::new(Vec2i(0, 192), (64, 2), &tileset, make_map(64, 2, 4..8));
+    let mut ground = Parallax::new(baked(&ground), (1.0, 1.0));
+    ground.params = opaque;
+    Ok(vec![sky, stars, ground])
+}
+
+pub fn make_font(assets: &mut Assets) -> Result<BitmapFont> {
+    BitmapFont::load(
+        assets,
+        Path::new("fonts/font5x7.png"),
+        Path::new("fonts/font5x7.fnt"),
+    )
 }
 
-pub fn make_menus() -> Vec<Rc<Texture>> {
-    let menu_1 = Rc::new(Texture::with_file(Path::new("llama/screens/play.png")));
-    let menu_2 = Rc::new(Texture::with_file(Path::new("llama/screens/load.png")));
-    let menu_3 = Rc::new(Texture::with_file(Path::new("llama/screens/tut.png")));
-    let help = Rc::new(Texture::with_file(Path::new("llama/screens/help.png")));
-    let game_over = Rc::new(Texture::with_file(Path::new(
-        "llama/screens/game_over.png",
-    )));
-    let next = Rc::new(Texture::with_file(Path::new("llama/screens/next.png")));
-    let win = Rc::new(Texture::with_file(Path::new("llama/screens/win.png")));
-    return vec![menu_1, menu_2, menu_3, help, next, win, game_over];
+pub fn make_menus(assets: &mut Assets) -> Result<Vec<Rc<Texture>>> {
+    let menu_1 = assets.texture(Path::new("llama/screens/play.png"))?;
+    let menu_2 = assets.texture(Path::new("llama/screens/load.png"))?;
+    let menu_3 = assets.texture(Path::new("llama/screens/tut.png"))?;
+    let help = assets.texture(Path::new("llama/screens/help.png"))?;
+    let game_over = assets.texture(Path::new("llama/screens/game_over.png"))?;
+    let next = assets.texture(Path::new("llama/screens/next.png"))?;
+    let win = assets.texture(Path::new("llama/screens/win.png"))?;
+    return Ok(vec![menu_1, menu_2, menu_3, help, next, win, game_over]);
 }
 
-pub fn level_1() -> Vec<Sprite> {
-    let mut sprites = make_core();
+pub fn level_1(assets: &mut Assets) -> Result<Vec<Sprite>> {
+    let mut sprites = make_core(assets)?;
     let asteroids = vec![
         Vec2i(150, 50),
         Vec2i(300, 160),
@@ -403,20 +549,20 @@ pub fn level_1() -> Vec<Sprite> {
             ];
             
             for position in asteroids {
-                sprites.push(make_asteroid(position));
+                sprites.push(make_asteroid(assets, position)?);
             }
             for position in little_asteroids{
-                sprites.push(make_little_asteroid(position));
+                sprites.push(make_little_asteroid(assets, position)?);
     }
     for position in break_asteroids{
-        sprites.push(make_break_asteroid(position));
+        sprites.push(make_break_asteroid(assets, position)?);
     }
     
-    return sprites;
+    return Ok(sprites);
 }
 
-pub fn level_2() -> Vec<Sprite> {
-    let mut sprites = make_core();
+pub fn level_2(assets: &mut Assets) -> Result<Vec<Sprite>> {
+    let mut sprites = make_core(assets)?;
     let asteroids = vec![
         Vec2i(170, 155),
         Vec2i(190, 125),
@@ -463,20 +609,20 @@ pub fn level_2() -> Vec<Sprite> {
         ];
 
     for position in asteroids {
-        sprites.push(make_asteroid(position));
+        sprites.push(make_asteroid(assets, position)?);
     }
     for position in little_asteroids{
-        sprites.push(make_little_asteroid(position));
+        sprites.push(make_little_asteroid(assets, position)?);
     }
     for position in break_asteroids{
-        sprites.push(make_break_asteroid(position));
+        sprites.push(make_break_asteroid(assets, position)?);
     }
     
-    return sprites;
+    return Ok(sprites);
 }
 
-pub fn level_3() -> Vec<Sprite> {
-    let mut sprites = make_core();
+pub fn level_3(assets: &mut Assets) -> Result<Vec<Sprite>> {
+    let mut sprites = make_core(assets)?;
     let asteroids = vec![
         Vec2i(210, 30),
         Vec2i(225, 140),
@@ -560,14 +706,14 @@ pub fn level_3() -> Vec<Sprite> {
                 ];
                 
     for position in asteroids {
-        sprites.push(make_asteroid(position));
+        sprites.push(make_asteroid(assets, position)?);
     }
     for position in little_asteroids{
-        sprites.push(make_little_asteroid(position));
+        sprites.push(make_little_asteroid(assets, position)?);
     }
     for position in break_asteroids{
-        sprites.push(make_break_asteroid(position));
+        sprites.push(make_break_asteroid(assets, position)?);
     }
     
-    return sprites;
+    return Ok(sprites);
 }
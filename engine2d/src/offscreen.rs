@@ -0,0 +1,53 @@
+use crate::screen::Screen;
+use crate::types::Vec2i;
+use image::{self, ImageFormat, ImageResult, RgbaImage};
+use std::path::Path;
+
+/// An owned framebuffer that we can draw into without a window or GPU surface.
+/// Useful for headless runs (CI) and for grabbing screenshots.
+pub struct Offscreen {
+    buffer: Vec<u8>,
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl Offscreen {
+    /// Make a new, fully transparent offscreen target
+    pub fn new(width: usize, height: usize) -> Self {
+        let depth = 4;
+        Self {
+            buffer: vec![0; width * height * depth],
+            width,
+            height,
+            depth,
+        }
+    }
+    /// Wrap our buffer in a `Screen` so `Tilemap::draw`, `draw_sprite` and friends can draw into it.
+    /// Just like `Screen::wrap`, call this every frame with the current camera position.
+    pub fn screen(&mut self, position: Vec2i) -> Screen<'_> {
+        Screen::wrap(
+            &mut self.buffer,
+            self.width,
+            self.height,
+            self.depth,
+            position,
+        )
+    }
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+    /// Copy the pixels out into an image.
+    /// The bytes are exactly what would be handed to `pixels`, i.e. premultiplied rgba8888.
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_raw(self.width as u32, self.height as u32, self.buffer.clone())
+            .expect("Offscreen buffer has the wrong size")
+    }
+    /// Save a PNG snapshot of whatever has been drawn so far
+    pub fn save_png(&self, path: &Path) -> ImageResult<()> {
+        self.to_image().save_with_format(path, ImageFormat::Png)
+    }
+}
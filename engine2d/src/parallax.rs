@@ -0,0 +1,106 @@
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::tile::Tilemap;
+use crate::types::*;
+use std::rc::Rc;
+
+/// What a parallax layer shows
+pub enum Backdrop {
+    /// A whole texture with its top-left at `position`
+    Texture {
+        texture: Rc<Texture>,
+        position: Vec2i,
+    },
+    /// A tilemap, wherever its own position puts it
+    Tilemap(Tilemap),
+}
+
+impl Backdrop {
+    /// The part of the layer's space the backdrop covers; copies are this far apart when it wraps
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Backdrop::Texture { texture, position } => {
+                let (w, h) = texture.size();
+                Rect {
+                    x: position.0,
+                    y: position.1,
+                    w: w as u16,
+                    h: h as u16,
+                }
+            }
+            Backdrop::Tilemap(map) => map.bounds(),
+        }
+    }
+
+    fn draw_with(&self, screen: &mut Screen, params: DrawParams) {
+        match self {
+            Backdrop::Texture { texture, position } => {
+                let (w, h) = texture.size();
+                let whole = Rect {
+                    x: 0,
+                    y: 0,
+                    w: w as u16,
+                    h: h as u16,
+                };
+                screen.bitblt_with(texture, whole, *position, params);
+            }
+            Backdrop::Tilemap(map) => map.draw_with(screen, params),
+        }
+    }
+}
+
+/// A background (or foreground) layer that scrolls at a fraction of the camera's speed,
+/// so things far away seem to drift by slower than the world does
+pub struct Parallax {
+    pub backdrop: Backdrop,
+    /// How far the layer scrolls for each pixel the camera moves, per axis.
+    /// 0.0 stays put on screen, 1.0 moves with the world and more than that rushes past in front of it.
+    pub factor: (f32, f32),
+    /// Whether the backdrop repeats forever horizontally and vertically
+    pub wrap: (bool, bool),
+    pub params: DrawParams,
+}
+
+impl Parallax {
+    /// A layer that doesn't wrap and draws with the default options
+    pub fn new(backdrop: Backdrop, factor: (f32, f32)) -> Self {
+        Self {
+            backdrop,
+            factor,
+            wrap: (false, false),
+            params: DrawParams::default(),
+        }
+    }
+
+    /// Draw the layer as seen from `screen`'s camera
+    pub fn draw(&self, screen: &mut Screen) {
+        let camera = screen.position;
+        let view = Vec2i(
+            (camera.0 as f32 * self.factor.0).round() as i32,
+            (camera.1 as f32 * self.factor.1).round() as i32,
+        );
+        let area = self.backdrop.bounds();
+        let view_size = screen.bounds();
+        // Where each copy of the backdrop starts along one axis: just the one unless it wraps,
+        // otherwise enough of them to cover the view, starting at or left of (above) its edge
+        let copies = |wrap: bool, view: i32, len: u16, lo: i32, span: u16| {
+            if !wrap || span == 0 {
+                return lo..lo + 1;
+            }
+            let span = span as i32;
+            let first = lo + (view - lo).div_euclid(span) * span;
+            let copies = (view + len as i32 - first + span - 1) / span;
+            first..first + copies * span
+        };
+        let xs = copies(self.wrap.0, view.0, view_size.w, area.x, area.w);
+        let ys = copies(self.wrap.1, view.1, view_size.h, area.y, area.h);
+        // Rather than moving the backdrop, move the camera the other way
+        for y in ys.step_by(area.h.max(1) as usize) {
+            for x in xs.clone().step_by(area.w.max(1) as usize) {
+                screen.position = Vec2i(view.0 - (x - area.x), view.1 - (y - area.y));
+                self.backdrop.draw_with(screen, self.params);
+            }
+        }
+        screen.position = camera;
+    }
+}
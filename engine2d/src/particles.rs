@@ -0,0 +1,225 @@
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+use std::rc::Rc;
+
+/// What each particle gets drawn as, centered on where it is
+#[derive(Clone)]
+pub enum Shape {
+    /// A filled square `size` pixels across
+    Square,
+    /// A filled circle `size` pixels across
+    Circle,
+    /// A frame of a texture, scaled so it's `size` pixels wide and tinted with the particle's color
+    Frame(Rc<Texture>, Rect),
+}
+
+/// One speck, in world coordinates. Speeds are in pixels per frame.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Particle {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    /// How many frames it's been around
+    pub age: usize,
+    /// How many frames it lasts
+    pub lifetime: usize,
+}
+
+impl Particle {
+    /// How far through its life it is, from 0.0 to 1.0
+    pub fn life(&self) -> f32 {
+        if self.lifetime == 0 {
+            1.0
+        } else {
+            self.age as f32 / self.lifetime as f32
+        }
+    }
+}
+
+/// Sprays particles from `position`, either all at once with `burst` or steadily at `rate`,
+/// then moves, ages, and draws them. Particles stay where they were put in the world, so the emitter
+/// can be moved around (or shared by every explosion) without dragging them along.
+/// Ranges are picked from at random for each particle; an empty range like `2.0..2.0` always gives its start.
+pub struct Emitter {
+    pub position: Vec2i,
+    pub shape: Shape,
+    /// Particles per frame while `update`ing; fractions add up over frames. 0.0 only bursts.
+    pub rate: f32,
+    /// How far from `position` particles start, in pixels
+    pub radius: f32,
+    /// Which way particles head off, in radians clockwise from the right (y points down on screen)
+    pub angle: Range<f32>,
+    pub speed: Range<f32>,
+    /// Added to every particle's downwards velocity each frame
+    pub gravity: f32,
+    /// Multiplies every particle's velocity each frame; 1.0 keeps them going forever
+    pub drag: f32,
+    /// How many frames particles last
+    pub lifetime: Range<usize>,
+    /// Particle color (straight alpha) at the start and end of its life, blended in between
+    pub color: (Rgba, Rgba),
+    /// Particle size in pixels at the start and end of its life
+    pub size: (f32, f32),
+    particles: Vec<Particle>,
+    // Part of a particle `rate` has owed us since the last one
+    owed: f32,
+    rng: StdRng,
+}
+
+impl Emitter {
+    /// An emitter that doesn't emit anything until told to: white, one pixel, 30 frame particles
+    /// flying off in every direction at one pixel per frame
+    pub fn new(position: Vec2i, shape: Shape) -> Self {
+        Self {
+            position,
+            shape,
+            rate: 0.0,
+            radius: 0.0,
+            angle: 0.0..std::f32::consts::PI * 2.0,
+            speed: 1.0..1.0,
+            gravity: 0.0,
+            drag: 1.0,
+            lifetime: 30..30,
+            color: (Rgba(255, 255, 255, 255), Rgba(255, 255, 255, 255)),
+            size: (1.0, 1.0),
+            particles: vec![],
+            owed: 0.0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Make the random choices repeatable, e.g. for tests
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Spray out `count` particles right now
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+
+    /// Spawn any particles `rate` calls for, then move and age everything by one frame,
+    /// dropping particles that have lived out their lifetime
+    pub fn update(&mut self) {
+        self.owed += self.rate;
+        while self.owed >= 1.0 {
+            self.owed -= 1.0;
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+        let (gravity, drag) = (self.gravity, self.drag);
+        for p in self.particles.iter_mut() {
+            p.velocity.1 += gravity;
+            p.velocity.0 *= drag;
+            p.velocity.1 *= drag;
+            p.position.0 += p.velocity.0;
+            p.position.1 += p.velocity.1;
+            p.age += 1;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+    }
+
+    /// Get rid of every particle, e.g. when a level restarts
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.owed = 0.0;
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Draw every particle, oldest first
+    pub fn draw(&self, screen: &mut Screen) {
+        for p in self.particles.iter() {
+            let t = p.life();
+            let color = lerp_color(self.color.0, self.color.1, t);
+            let size = self.size.0 + (self.size.1 - self.size.0) * t;
+            if color.3 == 0 || size <= 0.0 {
+                continue;
+            }
+            let (x, y) = p.position;
+            match &self.shape {
+                Shape::Square => {
+                    let side = size.round().max(1.0);
+                    let r = Rect {
+                        x: (x - side / 2.0).round() as i32,
+                        y: (y - side / 2.0).round() as i32,
+                        w: side as u16,
+                        h: side as u16,
+                    };
+                    screen.fill_rect(r, color);
+                }
+                Shape::Circle => {
+                    let center = Vec2i(x.round() as i32, y.round() as i32);
+                    screen.fill_circle(center, (size / 2.0).round() as u16, color);
+                }
+                Shape::Frame(texture, frame) => {
+                    let (w, h) = (frame.w as f32, frame.h as f32);
+                    let scale = size / w.max(1.0);
+                    let transform = Transform {
+                        rotation: 0.0,
+                        scale: (scale, scale),
+                        pivot: (w / 2.0, h / 2.0),
+                    };
+                    let params = DrawParams {
+                        tint: color,
+                        ..DrawParams::default()
+                    };
+                    let to = Vec2i((x - w / 2.0).round() as i32, (y - h / 2.0).round() as i32);
+                    screen.bitblt_transformed(texture, *frame, to, transform, params);
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self) -> Particle {
+        let rng = &mut self.rng;
+        let angle = pick(rng, &self.angle);
+        let speed = pick(rng, &self.speed);
+        // Spread them evenly over the disc rather than bunching them in the middle
+        let offset = self.radius * pick(rng, &(0.0..1.0)).sqrt();
+        let around = pick(rng, &(0.0..std::f32::consts::PI * 2.0));
+        let lifetime = if self.lifetime.start < self.lifetime.end {
+            rng.gen_range(self.lifetime.start, self.lifetime.end)
+        } else {
+            self.lifetime.start
+        };
+        Particle {
+            position: (
+                self.position.0 as f32 + offset * around.cos(),
+                self.position.1 as f32 + offset * around.sin(),
+            ),
+            velocity: (speed * angle.cos(), speed * angle.sin()),
+            age: 0,
+            lifetime,
+        }
+    }
+}
+
+fn pick(rng: &mut StdRng, range: &Range<f32>) -> f32 {
+    if range.start < range.end {
+        rng.gen_range(range.start, range.end)
+    } else {
+        range.start
+    }
+}
+
+fn lerp_color(Rgba(r0, g0, b0, a0): Rgba, Rgba(r1, g1, b1, a1): Rgba, t: f32) -> Rgba {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgba(mix(r0, r1), mix(g0, g1), mix(b0, b1), mix(a0, a1))
+}
@@ -0,0 +1,83 @@
+use crate::types::Rgba;
+
+/// A way to change a texture's colors, see `Texture::recolored`
+#[derive(PartialEq, Clone, Debug)]
+pub enum Recolor {
+    /// Swap each `from` color for its `to` color and leave everything else alone.
+    /// Colors are straight (not premultiplied) and only their RGB matters; every pixel keeps its own alpha.
+    Palette(Vec<(Rgba, Rgba)>),
+    /// Rotate every color's hue by this many degrees, keeping its saturation and brightness
+    HueShift(f32),
+}
+
+impl Recolor {
+    /// Recolor one premultiplied pixel, keeping its alpha
+    pub fn apply(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        if a == 0 {
+            return [r, g, b, a];
+        }
+        match self {
+            Recolor::Palette(swaps) => {
+                // Compare in premultiplied space, so edge pixels match exactly the way Texture::new made them
+                for &(from, to) in swaps {
+                    if premultiply(from, a) == [r, g, b] {
+                        let [r, g, b] = premultiply(to, a);
+                        return [r, g, b, a];
+                    }
+                }
+                [r, g, b, a]
+            }
+            Recolor::HueShift(degrees) => {
+                let straight = |c: u8| (c as f32 * 255.0 / a as f32).min(255.0);
+                let (h, s, v) = rgb_to_hsv(straight(r), straight(g), straight(b));
+                let (r, g, b) = hsv_to_rgb((h + degrees).rem_euclid(360.0), s, v);
+                let shifted = Rgba(r.round() as u8, g.round() as u8, b.round() as u8, a);
+                let [r, g, b] = premultiply(shifted, a);
+                [r, g, b, a]
+            }
+        }
+    }
+}
+
+// Premultiply just like Texture::new does (truncating)
+fn premultiply(Rgba(r, g, b, _): Rgba, a: u8) -> [u8; 3] {
+    let a = a as f32 / 255.0;
+    [
+        (r as f32 * a) as u8,
+        (g as f32 * a) as u8,
+        (b as f32 * a) as u8,
+    ]
+}
+
+// Components 0..=255 in, hue in degrees and saturation/value in 0..=1 out
+fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    (h, s, max / 255.0)
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    ((r + m) * 255.0, (g + m) * 255.0, (b + m) * 255.0)
+}
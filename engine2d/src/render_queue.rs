@@ -0,0 +1,131 @@
+use crate::batch::Batch;
+use crate::parallax::Parallax;
+use crate::particles::Emitter;
+use crate::screen::Screen;
+use crate::sprite::{DrawSpriteExt, Sprite};
+use crate::terrain::Terrain;
+use crate::tile::Tilemap;
+use crate::types::DrawParams;
+
+/// The broad passes a frame is drawn in, back to front
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Layer {
+    /// Scenery behind everything: parallax layers, tilemaps
+    Background,
+    /// Sprites and anything else that's part of the game world
+    World,
+    /// Explosions, sparks and other things over the world
+    Effects,
+    /// Scores, meters and the like, drawn in screen coordinates on top of it all
+    Hud,
+}
+
+/// Something to draw, borrowed from wherever it lives
+pub enum Item<'a> {
+    Tilemap(&'a Tilemap, DrawParams),
+    Parallax(&'a Parallax),
+    Sprite(&'a Sprite),
+    Particles(&'a Emitter),
+    Terrain(&'a Terrain),
+    /// Anything else, like text or debug outlines
+    Custom(Box<dyn FnOnce(&mut Screen) + 'a>),
+}
+
+/// Collects everything to draw this frame, then draws it sorted by layer and, within a layer,
+/// by depth (lowest first). Things with the same layer and depth are drawn in the order they were pushed.
+/// Build a new one every frame.
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    items: Vec<(Layer, i32, Item<'a>)>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    pub fn push(&mut self, layer: Layer, depth: i32, item: Item<'a>) {
+        self.items.push((layer, depth, item));
+    }
+
+    pub fn tilemap(&mut self, layer: Layer, depth: i32, map: &'a Tilemap, params: DrawParams) {
+        self.push(layer, depth, Item::Tilemap(map, params));
+    }
+
+    pub fn parallax(&mut self, layer: Layer, depth: i32, parallax: &'a Parallax) {
+        self.push(layer, depth, Item::Parallax(parallax));
+    }
+
+    pub fn sprite(&mut self, layer: Layer, depth: i32, sprite: &'a Sprite) {
+        self.push(layer, depth, Item::Sprite(sprite));
+    }
+
+    pub fn particles(&mut self, layer: Layer, depth: i32, emitter: &'a Emitter) {
+        self.push(layer, depth, Item::Particles(emitter));
+    }
+
+    pub fn terrain(&mut self, layer: Layer, depth: i32, terrain: &'a Terrain) {
+        self.push(layer, depth, Item::Terrain(terrain));
+    }
+
+    pub fn custom(&mut self, layer: Layer, depth: i32, draw: impl FnOnce(&mut Screen) + 'a) {
+        self.push(layer, depth, Item::Custom(Box::new(draw)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Draw everything in order; the HUD layer is drawn with the camera at the origin (see `Screen::hud`).
+    /// Tilemaps and runs of sprites next to each other in the order are drawn on all the cores at once.
+    pub fn draw(mut self, screen: &mut Screen) {
+        // A stable sort, so ties stay in the order they were pushed
+        self.items.sort_by_key(|&(layer, depth, _)| (layer, depth));
+        let mut sprites = Batch::new();
+        let mut sprites_layer = Layer::Background;
+        for (layer, _, item) in self.items {
+            let is_sprite = matches!(item, Item::Sprite(_));
+            if !sprites.is_empty() && (layer != sprites_layer || !is_sprite) {
+                in_layer(screen, sprites_layer, |screen| {
+                    sprites.draw_parallel(screen)
+                });
+                sprites.clear();
+            }
+            match item {
+                Item::Sprite(sprite) => {
+                    sprites.push(sprite.blit());
+                    sprites_layer = layer;
+                }
+                item => in_layer(screen, layer, |screen| draw_item(screen, item)),
+            }
+        }
+        if !sprites.is_empty() {
+            in_layer(screen, sprites_layer, |screen| {
+                sprites.draw_parallel(screen)
+            });
+        }
+    }
+}
+
+fn in_layer<'fb>(screen: &mut Screen<'fb>, layer: Layer, draw: impl FnOnce(&mut Screen<'fb>)) {
+    if layer == Layer::Hud {
+        screen.hud(draw);
+    } else {
+        draw(screen);
+    }
+}
+
+fn draw_item(screen: &mut Screen, item: Item) {
+    match item {
+        Item::Tilemap(map, params) => map.draw_parallel(screen, params),
+        Item::Parallax(parallax) => parallax.draw(screen),
+        Item::Sprite(sprite) => screen.draw_sprite(sprite),
+        Item::Particles(emitter) => emitter.draw(screen),
+        Item::Terrain(terrain) => terrain.draw(screen),
+        Item::Custom(draw) => draw(screen),
+    }
+}
@@ -1,12 +1,21 @@
 // We can pull in definitions from elsewhere in the crate!
+use crate::dirty::Draw;
 use crate::texture::Texture;
-use crate::types::{Rect, Rgba, Vec2i};
+use crate::types::{BlendMode, DrawParams, Rect, Rgba, Transform, Vec2i};
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 pub struct Screen<'fb> {
     framebuffer: &'fb mut [u8],
     width: usize,
     height: usize,
     depth: usize,
     pub position: Vec2i,
+    // Drawing only touches this part of the screen, in screen coordinates
+    clip: Rect,
+    // Every draw call so far, while `DirtyRects` is keeping track
+    log: Option<Vec<Draw>>,
 }
 impl<'fb> Screen<'fb> {
     // Call =wrap= every frame; that means the camera position will need to be stored in the game state
@@ -23,6 +32,13 @@ impl<'fb> Screen<'fb> {
             height,
             depth,
             position,
+            clip: Rect {
+                x: 0,
+                y: 0,
+                w: width as u16,
+                h: height as u16,
+            },
+            log: None,
         }
     }
     pub fn bounds(&self) -> Rect {
@@ -33,30 +49,345 @@ impl<'fb> Screen<'fb> {
             h: self.height as u16,
         }
     }
+    /// The part of the screen drawing can touch, in screen coordinates; the whole screen unless it's been `set_clip`
+    pub fn clip(&self) -> Rect {
+        self.clip
+    }
+    /// Keep drawing inside `area` (in screen coordinates) from now on, e.g. to redraw just part of the screen
+    pub fn set_clip(&mut self, area: Rect) {
+        let whole = Rect {
+            x: 0,
+            y: 0,
+            w: self.width as u16,
+            h: self.height as u16,
+        };
+        self.clip = whole.intersection(area);
+    }
+    // Left, top, right and bottom edges of the clip rect (the last two just past it)
+    fn clip_edges(&self) -> (i32, i32, i32, i32) {
+        let Rect { x, y, w, h } = self.clip;
+        (x, y, x + w as i32, y + h as i32)
+    }
+    // Start or stop keeping a log of draw calls
+    pub(crate) fn set_logging(&mut self, on: bool) {
+        self.log = if on { Some(vec![]) } else { None };
+    }
+    pub(crate) fn is_logging(&self) -> bool {
+        self.log.is_some()
+    }
+    pub(crate) fn take_log(&mut self) -> Vec<Draw> {
+        self.log.take().unwrap_or_default()
+    }
+    // Log a draw call that could have touched `area` (in screen coordinates), along with everything
+    // that decides what it looks like, if anyone's keeping track
+    fn note(&mut self, area: Rect, what: impl Hash) {
+        if let Some(log) = &mut self.log {
+            let whole = Rect {
+                x: 0,
+                y: 0,
+                w: self.width as u16,
+                h: self.height as u16,
+            };
+            let area = whole.intersection(area);
+            if !area.is_empty() {
+                let mut hasher = DefaultHasher::new();
+                what.hash(&mut hasher);
+                log.push(Draw {
+                    area,
+                    key: hasher.finish(),
+                });
+            }
+        }
+    }
+    /// Run `draw` with the camera at the origin, so everything it draws is in screen coordinates.
+    /// This is the HUD pass: scores, meters and menus that shouldn't scroll or shake with the world.
+    pub fn hud(&mut self, draw: impl FnOnce(&mut Screen<'fb>)) {
+        let camera = self.position;
+        self.position = Vec2i(0, 0);
+        draw(self);
+        self.position = camera;
+    }
+    /// Copy everything drawn so far into a texture, e.g. to keep showing the old scene during a transition
+    pub fn capture(&self) -> Texture {
+        Texture::from_premultiplied(self.width, self.height, self.framebuffer.to_vec())
+    }
+    /// Split the screen into horizontal bands of `rows` rows each (the last may be shorter),
+    /// which can be drawn into separately, e.g. on different threads.
+    /// Every band sees the world through the same camera (and clip rect), so drawing something into each band
+    /// draws it just like drawing it into the whole screen would. Bands don't log their draw calls.
+    pub fn bands(&mut self, rows: usize) -> Vec<Screen<'_>> {
+        let rows = rows.max(1);
+        let (width, depth, Vec2i(x, y)) = (self.width, self.depth, self.position);
+        let clip = self.clip;
+        let pitch = width * depth;
+        self.framebuffer
+            .chunks_mut(pitch * rows)
+            .enumerate()
+            .map(|(i, band)| {
+                let top = (i * rows) as i32;
+                let height = band.len() / pitch;
+                let rows = Rect {
+                    x: 0,
+                    y: top,
+                    w: width as u16,
+                    h: height as u16,
+                };
+                let clip = clip.intersection(rows);
+                Screen {
+                    height,
+                    framebuffer: band,
+                    width,
+                    depth,
+                    position: Vec2i(x, y + top),
+                    clip: Rect {
+                        y: clip.y - top,
+                        ..clip
+                    },
+                    log: None,
+                }
+            })
+            .collect()
+    }
     // Clear's the same...
     pub fn clear(&mut self, col: Rgba) {
         let c = [col.0, col.1, col.2, col.3];
-        for px in self.framebuffer.chunks_exact_mut(4) {
-            px.copy_from_slice(&c);
+        // It covers the whole screen as far as what's drawn goes, wherever the clip happens to be
+        let whole = Rect {
+            x: 0,
+            y: 0,
+            ..self.bounds()
+        };
+        self.note(whole, ("clear", c));
+        if self.clip.is_empty() {
+            return;
+        }
+        let (x0, y0, x1, y1) = self.clip_edges();
+        let pitch = self.width * self.depth;
+        let rows = &mut self.framebuffer[(pitch * y0 as usize)..(pitch * y1 as usize)];
+        for row in rows.chunks_exact_mut(pitch) {
+            for px in row[(4 * x0 as usize)..(4 * x1 as usize)].chunks_exact_mut(4) {
+                px.copy_from_slice(&c);
+            }
+        }
+    }
+
+    // Vector drawing: everything takes world coordinates and (straight alpha) colors,
+    // and composites "over" what's already there.
+
+    // Plot one pixel
+    pub fn put_pixel(&mut self, Vec2i(x, y): Vec2i, col: Rgba) {
+        let px = premultiplied(col);
+        let (x, y) = (x - self.position.0, y - self.position.1);
+        self.note(Rect { x, y, w: 1, h: 1 }, ("pixel", px));
+        self.plot(x, y, px);
+    }
+
+    // Fill a whole rectangle
+    pub fn fill_rect(&mut self, r: Rect, col: Rgba) {
+        let px = premultiplied(col);
+        let x = r.x - self.position.0;
+        let y = r.y - self.position.1;
+        self.note(Rect { x, y, ..r }, ("fill", px));
+        let (_, top, _, bottom) = self.clip_edges();
+        for row in y.max(top)..(y + r.h as i32).min(bottom) {
+            self.span(row, x, x + r.w as i32, px);
+        }
+    }
+
+    // Draw the one pixel wide outline of a rectangle (just inside its bounds)
+    pub fn draw_rect(&mut self, r: Rect, col: Rgba) {
+        if r.w == 0 || r.h == 0 {
+            return;
+        }
+        let (w, h) = (r.w as i32, r.h as i32);
+        self.fill_rect(Rect { h: 1, ..r }, col);
+        if h > 1 {
+            self.fill_rect(
+                Rect {
+                    y: r.y + h - 1,
+                    h: 1,
+                    ..r
+                },
+                col,
+            );
+        }
+        if h > 2 {
+            let sides = Rect {
+                y: r.y + 1,
+                w: 1,
+                h: r.h - 2,
+                ..r
+            };
+            self.fill_rect(sides, col);
+            if w > 1 {
+                self.fill_rect(
+                    Rect {
+                        x: r.x + w - 1,
+                        ..sides
+                    },
+                    col,
+                );
+            }
+        }
+    }
+
+    // Bresenham's line, including both end points. Only the steps inside the clip rect get taken,
+    // and they land on exactly the pixels the whole line would have.
+    pub fn line(&mut self, Vec2i(x0, y0): Vec2i, Vec2i(x1, y1): Vec2i, col: Rgba) {
+        let px = premultiplied(col);
+        let (x0, y0) = (x0 - self.position.0, y0 - self.position.1);
+        let (x1, y1) = (x1 - self.position.0, y1 - self.position.1);
+        let area = screen_area(
+            self.width,
+            self.height,
+            (x0.min(x1) as i64, y0.min(y1) as i64),
+            (x0.max(x1) as i64 + 1, y0.max(y1) as i64 + 1),
+        );
+        self.note(area, ("line", x0, y0, x1, y1, px));
+        // One pixel along the longer axis every step, and the nearest whole pixel along the other
+        let (dx, dy) = (x1 as i64 - x0 as i64, y1 as i64 - y0 as i64);
+        let steps = dx.abs().max(dy.abs());
+        let along = |d: i64, k: i64| {
+            if steps == 0 {
+                0
+            } else {
+                d.signum() * ((2 * k * d.abs() + steps) / (2 * steps))
+            }
+        };
+        // Each coordinate only ever moves one way, so the steps inside the clip rect are one unbroken run
+        let (left, top, right, bottom) = self.clip_edges();
+        let mut first = 0;
+        let mut last = steps;
+        for &(start, d, lo, hi) in [(x0, dx, left, right), (y0, dy, top, bottom)].iter() {
+            let at = |k| start as i64 + along(d, k);
+            let (lo, hi) = (lo as i64, hi as i64);
+            if d >= 0 {
+                first = first.max(first_step(steps, |k| at(k) >= lo));
+                last = last.min(first_step(steps, |k| at(k) >= hi) - 1);
+            } else {
+                first = first.max(first_step(steps, |k| at(k) < hi));
+                last = last.min(first_step(steps, |k| at(k) < lo) - 1);
+            }
+        }
+        for k in first..=last {
+            let (x, y) = (x0 as i64 + along(dx, k), y0 as i64 + along(dy, k));
+            self.plot(x as i32, y as i32, px);
+        }
+    }
+
+    // Midpoint circle outline
+    pub fn draw_circle(&mut self, Vec2i(cx, cy): Vec2i, radius: u16, col: Rgba) {
+        let px = premultiplied(col);
+        let (cx, cy) = (cx - self.position.0, cy - self.position.1);
+        let area = circle_area(self.width, self.height, cx, cy, radius);
+        self.note(area, ("circle", cx, cy, radius, px));
+        let (mut x, mut y) = (radius as i32, 0);
+        let mut err = 1 - x;
+        while x >= y {
+            let octants = [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ];
+            for (i, &(ox, oy)) in octants.iter().enumerate() {
+                // Where octants meet some of these points coincide; only plot each one once
+                if !octants[..i].contains(&(ox, oy)) {
+                    self.plot(cx + ox, cy + oy, px);
+                }
+            }
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    // Filled circle, every pixel whose middle is within `radius` of the center pixel's middle
+    pub fn fill_circle(&mut self, Vec2i(cx, cy): Vec2i, radius: u16, col: Rgba) {
+        let px = premultiplied(col);
+        let (cx, cy) = (cx - self.position.0, cy - self.position.1);
+        let area = circle_area(self.width, self.height, cx, cy, radius);
+        self.note(area, ("disc", cx, cy, radius, px));
+        // Squaring a big radius doesn't fit in an i32
+        let r = radius as i64;
+        let (_, top, _, bottom) = self.clip_edges();
+        let (cx, cy) = (cx as i64, cy as i64);
+        for dy in (-r).max(top as i64 - cy)..=r.min(bottom as i64 - 1 - cy) {
+            let half = ((r * r - dy * dy) as f64).sqrt() as i64;
+            let (x0, x1) = (cx - half, cx + half + 1);
+            // Clamped to somewhere off screen, so it still fits in an i32
+            let clamp = |x: i64| x.max(-1).min(self.width as i64 + 1) as i32;
+            self.span((cy + dy) as i32, clamp(x0), clamp(x1), px);
+        }
+    }
+
+    // Composite a premultiplied pixel at screen coordinates, if it's inside the clip rect
+    fn plot(&mut self, x: i32, y: i32, px: [u8; 4]) {
+        let (x0, y0, x1, y1) = self.clip_edges();
+        if x < x0 || y < y0 || x >= x1 || y >= y1 {
+            return;
+        }
+        let i = (y as usize * self.width + x as usize) * self.depth;
+        over(&mut self.framebuffer[i..(i + self.depth)], &px);
+    }
+
+    // Composite a premultiplied pixel over screen row `y` from `x0` up to (not including) `x1`, clipped to the clip rect
+    fn span(&mut self, y: i32, x0: i32, x1: i32, px: [u8; 4]) {
+        let (left, top, right, bottom) = self.clip_edges();
+        let (x0, x1) = (x0.max(left), x1.min(right));
+        if y < top || y >= bottom || x0 >= x1 {
+            return;
+        }
+        let row = y as usize * self.width;
+        let depth = self.depth;
+        for to in self.framebuffer[((row + x0 as usize) * depth)..((row + x1 as usize) * depth)]
+            .chunks_exact_mut(depth)
+        {
+            over(to, &px);
         }
     }
 
     // Bitblt too begins with a translation
-    pub fn bitblt(&mut self, src: &Texture, from: Rect, Vec2i(to_x, to_y): Vec2i) {
+    pub fn bitblt(&mut self, src: &Texture, from: Rect, to: Vec2i) {
+        self.bitblt_with(src, from, to, DrawParams::default());
+    }
+
+    // Same as bitblt, but with extra options like flipping, tinting, fading and blend modes
+    pub fn bitblt_with(
+        &mut self,
+        src: &Texture,
+        from: Rect,
+        Vec2i(to_x, to_y): Vec2i,
+        params: DrawParams,
+    ) {
         let (tw, th) = src.size();
-        assert!(0 <= from.x);
-        assert!(from.x < tw as i32);
-        assert!(0 <= from.y);
-        assert!(from.y < th as i32);
+        assert!(
+            src.valid_frame(from),
+            "Frame {:?} runs outside the {}x{} texture",
+            from,
+            tw,
+            th
+        );
         let to_x = to_x - self.position.0;
         let to_y = to_y - self.position.1;
-        if (to_x + from.w as i32) < 0
-            || (self.width as i32) <= to_x
-            || (to_y + from.h as i32) < 0
-            || (self.height as i32) <= to_y
-        {
-            return;
-        }
+        let area = Rect {
+            x: to_x,
+            y: to_y,
+            ..from
+        };
+        self.note(
+            area,
+            ("blit", src.id(), from, to_x, to_y, params_key(params)),
+        );
+        let (left, top, right, bottom) = self.clip_edges();
         let depth = self.depth;
         assert_eq!(depth, src.depth());
         let src_pitch = src.pitch();
@@ -64,35 +395,390 @@ impl<'fb> Screen<'fb> {
         // All this rigmarole is just to avoid bounds checks on each pixel of the blit.
         // We want to calculate which row/col of the src image to start at and which to end at.
         // This way there's no need to even check for out of bounds draws.
-        let y_skip = to_y.max(0) - to_y;
-        let x_skip = to_x.max(0) - to_x;
-        let y_count = (to_y + from.h as i32).min(self.height as i32) - to_y;
-        let x_count = (to_x + from.w as i32).min(self.width as i32) - to_x;
+        let y_skip = to_y.max(top) - to_y;
+        let x_skip = to_x.max(left) - to_x;
+        let y_count = (to_y + from.h as i32).min(bottom) - to_y;
+        let x_count = (to_x + from.w as i32).min(right) - to_x;
+        if y_skip >= y_count || x_skip >= x_count {
+            return;
+        }
+        // A flipped blit reads the mirror image of the rows/cols it would otherwise read,
+        // and then walks through them backwards.
+        let (src_y0, src_y1) = if params.flip_y {
+            (from.h as i32 - y_count, from.h as i32 - y_skip)
+        } else {
+            (y_skip, y_count)
+        };
+        let (src_x0, src_x1) = if params.flip_x {
+            (from.w as i32 - x_count, from.w as i32 - x_skip)
+        } else {
+            (x_skip, x_count)
+        };
         let src_buf = src.buffer();
-        for (row_a, row_b) in src_buf[(src_pitch * ((from.y + y_skip) as usize))
-            ..(src_pitch * ((from.y + y_count) as usize))]
-            .chunks_exact(src_pitch)
-            .zip(
-                self.framebuffer[(dst_pitch * ((to_y + y_skip) as usize))
-                    ..(dst_pitch * ((to_y + y_count) as usize))]
-                    .chunks_exact_mut(dst_pitch),
-            )
-        {
-            let to_cols = row_b
-                [(depth * (to_x + x_skip) as usize)..(depth * (to_x + x_count) as usize)]
-                .chunks_exact_mut(depth);
-            let from_cols = row_a
-                [(depth * (from.x + x_skip) as usize)..(depth * (from.x + x_count) as usize)]
-                .chunks_exact(depth);
-            // Composite over, assume premultiplied rgba8888
+        let src_rows = src_buf[(src_pitch * ((from.y + src_y0) as usize))
+            ..(src_pitch * ((from.y + src_y1) as usize))]
+            .chunks_exact(src_pitch);
+        let dst_rows = self.framebuffer
+            [(dst_pitch * ((to_y + y_skip) as usize))..(dst_pitch * ((to_y + y_count) as usize))]
+            .chunks_exact_mut(dst_pitch);
+        let src_cols = (depth * (from.x + src_x0) as usize)..(depth * (from.x + src_x1) as usize);
+        let dst_cols = (depth * (to_x + x_skip) as usize)..(depth * (to_x + x_count) as usize);
+        if params.flip_y {
+            blit_rows(src_rows.rev(), dst_rows, src_cols, dst_cols, depth, params);
+        } else {
+            blit_rows(src_rows, dst_rows, src_cols, dst_cols, depth, params);
+        }
+    }
+
+    // The slow path: rotate and scale the source rect around `transform.pivot` while drawing it.
+    // Without any rotation or scaling this draws the same pixels as bitblt_with.
+    pub fn bitblt_transformed(
+        &mut self,
+        src: &Texture,
+        from: Rect,
+        Vec2i(to_x, to_y): Vec2i,
+        transform: Transform,
+        params: DrawParams,
+    ) {
+        let (tw, th) = src.size();
+        assert!(
+            src.valid_frame(from),
+            "Frame {:?} runs outside the {}x{} texture",
+            from,
+            tw,
+            th
+        );
+        let depth = self.depth;
+        assert_eq!(depth, src.depth());
+        let Transform {
+            rotation,
+            scale: (sx, sy),
+            pivot: (px, py),
+        } = transform;
+        if sx == 0.0 || sy == 0.0 {
+            return;
+        }
+        // The pivot in screen space
+        let (to_x, to_y) = (to_x - self.position.0, to_y - self.position.1);
+        let cx = to_x as f32 + px;
+        let cy = to_y as f32 + py;
+        let (sin, cos) = rotation.sin_cos();
+        // Find the screen-space box the transformed rect covers by pushing its corners through the transform
+        let (w, h) = (from.w as f32, from.h as f32);
+        let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for &(x, y) in &[(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)] {
+            let (lx, ly) = ((x - px) * sx, (y - py) * sy);
+            let (x, y) = (lx * cos - ly * sin + cx, lx * sin + ly * cos + cy);
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        let (x0, y0) = (x0.floor() as i32, y0.floor() as i32);
+        let (x1, y1) = (x1.ceil() as i32, y1.ceil() as i32);
+        // It could be huge when scaled right up, so keep it to the screen before it goes in a Rect
+        let (ax0, ay0) = (x0.max(0), y0.max(0));
+        let area = Rect {
+            x: ax0,
+            y: ay0,
+            w: (x1.min(self.width as i32) - ax0).max(0) as u16,
+            h: (y1.min(self.height as i32) - ay0).max(0) as u16,
+        };
+        let shape = [rotation, sx, sy, px, py].map(f32::to_bits);
+        let key = ("transformed", src.id(), from, to_x, to_y, shape);
+        self.note(area, (key, params_key(params)));
+        // ... and clip that to the clip rect
+        let (left, top, right, bottom) = self.clip_edges();
+        let (x0, y0, x1, y1) = (x0.max(left), y0.max(top), x1.min(right), y1.min(bottom));
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+        let src_pitch = src.pitch();
+        let dst_pitch = self.width * depth;
+        let src_buf = src.buffer();
+        let mul = modulation(params);
+        for y in y0..y1 {
+            let row =
+                &mut self.framebuffer[(dst_pitch * y as usize)..(dst_pitch * (y + 1) as usize)];
+            for x in x0..x1 {
+                // Map the middle of this screen pixel back into the source rect (nearest neighbour)
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let u = (dx * cos + dy * sin) / sx + px;
+                let v = (-dx * sin + dy * cos) / sy + py;
+                if u < 0.0 || v < 0.0 || u >= w || v >= h {
+                    continue;
+                }
+                let (mut u, mut v) = (u as i32, v as i32);
+                if params.flip_x {
+                    u = from.w as i32 - 1 - u;
+                }
+                if params.flip_y {
+                    v = from.h as i32 - 1 - v;
+                }
+                let i = src_pitch * (from.y + v) as usize + depth * (from.x + u) as usize;
+                let j = depth * x as usize;
+                let from = &src_buf[i..(i + depth)];
+                match mul {
+                    None => blend(params.blend, &mut row[j..(j + depth)], from),
+                    Some(mul) => {
+                        blend(params.blend, &mut row[j..(j + depth)], &modulate(from, mul))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn blit_rows<'a, 'b>(
+    src_rows: impl Iterator<Item = &'a [u8]>,
+    dst_rows: impl Iterator<Item = &'b mut [u8]>,
+    src_cols: Range<usize>,
+    dst_cols: Range<usize>,
+    depth: usize,
+    params: DrawParams,
+) {
+    let mul = modulation(params);
+    for (row_a, row_b) in src_rows.zip(dst_rows) {
+        let to_cols = &mut row_b[dst_cols.clone()];
+        let from_cols = &row_a[src_cols.clone()];
+        // Straight copies don't need to look at individual pixels at all
+        if params.blend == BlendMode::Copy && !params.flip_x && mul.is_none() {
+            to_cols.copy_from_slice(from_cols);
+            continue;
+        }
+        // Neither do plain "over" blits, mostly
+        if params.blend == BlendMode::Over && !params.flip_x && mul.is_none() && depth == 4 {
+            over_row(to_cols, from_cols);
+            continue;
+        }
+        let to_cols = to_cols.chunks_exact_mut(depth);
+        let from_cols = from_cols.chunks_exact(depth);
+        if params.flip_x {
+            composite(from_cols.rev(), to_cols, mul, params.blend);
+        } else {
+            composite(from_cols, to_cols, mul, params.blend);
+        }
+    }
+}
+
+fn composite<'a, 'b>(
+    from_cols: impl Iterator<Item = &'a [u8]>,
+    to_cols: impl Iterator<Item = &'b mut [u8]>,
+    mul: Option<[u16; 4]>,
+    mode: BlendMode,
+) {
+    // Pick the blend function once per row rather than once per pixel
+    match mode {
+        BlendMode::Over => composite_with(from_cols, to_cols, mul, over),
+        BlendMode::Additive => composite_with(from_cols, to_cols, mul, additive),
+        BlendMode::Multiply => composite_with(from_cols, to_cols, mul, multiply),
+        BlendMode::Screen => composite_with(from_cols, to_cols, mul, screen),
+        BlendMode::Copy => composite_with(from_cols, to_cols, mul, copy),
+    }
+}
+
+fn composite_with<'a, 'b>(
+    from_cols: impl Iterator<Item = &'a [u8]>,
+    to_cols: impl Iterator<Item = &'b mut [u8]>,
+    mul: Option<[u16; 4]>,
+    op: impl Fn(&mut [u8], &[u8]),
+) {
+    match mul {
+        None => {
+            for (to, from) in to_cols.zip(from_cols) {
+                op(to, from);
+            }
+        }
+        Some(mul) => {
             for (to, from) in to_cols.zip(from_cols) {
-                let ta = to[3] as f32 / 255.0;
-                let fa = from[3] as f32 / 255.0;
-                for i in 0..3 {
-                    to[i] = from[i].saturating_add((to[i] as f32 * (1.0 - fa)).round() as u8);
+                op(to, &modulate(from, mul));
+            }
+        }
+    }
+}
+
+fn blend(mode: BlendMode, to: &mut [u8], from: &[u8]) {
+    match mode {
+        BlendMode::Over => over(to, from),
+        BlendMode::Additive => additive(to, from),
+        BlendMode::Multiply => multiply(to, from),
+        BlendMode::Screen => screen(to, from),
+        BlendMode::Copy => copy(to, from),
+    }
+}
+
+// Everything about `params` that changes what a draw looks like, in a form that can be hashed
+fn params_key(params: DrawParams) -> impl Hash {
+    (
+        params.flip_x,
+        params.flip_y,
+        params.tint,
+        params.opacity.to_bits(),
+        params.blend,
+    )
+}
+
+// The box a circle of `radius` around screen pixel (cx, cy) fits in, as much of it as is on a `width` x `height` screen
+fn circle_area(width: usize, height: usize, cx: i32, cy: i32, radius: u16) -> Rect {
+    let (cx, cy, r) = (cx as i64, cy as i64, radius as i64);
+    screen_area(width, height, (cx - r, cy - r), (cx + r + 1, cy + r + 1))
+}
+
+// The part of a `width` x `height` screen from the top-left corner up to (not including) the bottom-right,
+// which can be far enough off screen that the whole box wouldn't fit in a `Rect`
+fn screen_area(width: usize, height: usize, (x0, y0): (i64, i64), (x1, y1): (i64, i64)) -> Rect {
+    let (x0, y0) = (x0.max(0), y0.max(0));
+    let (x1, y1) = (x1.min(width as i64).max(x0), y1.min(height as i64).max(y0));
+    Rect {
+        x: x0 as i32,
+        y: y0 as i32,
+        w: (x1 - x0) as u16,
+        h: (y1 - y0) as u16,
+    }
+}
+
+// The first step `k` out of 0..=`steps` where `past(k)`, or `steps + 1` if there isn't one.
+// `past` has to stay true from then on.
+fn first_step(steps: i64, past: impl Fn(i64) -> bool) -> i64 {
+    let (mut lo, mut hi) = (0, steps + 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if past(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    lo
+}
+
+fn premultiplied(Rgba(r, g, b, a): Rgba) -> [u8; 4] {
+    let alpha = a as f32 / 255.0;
+    [
+        (r as f32 * alpha).round() as u8,
+        (g as f32 * alpha).round() as u8,
+        (b as f32 * alpha).round() as u8,
+        a,
+    ]
+}
+
+// Per-channel factors (out of 255) for the tint and opacity, or None if they wouldn't change anything.
+// Since the source is premultiplied, the color channels get scaled by the tint's alpha too.
+// The floating point math happens once per draw here, so the per-pixel work can stay in integers.
+fn modulation(params: DrawParams) -> Option<[u16; 4]> {
+    let Rgba(r, g, b, a) = params.tint;
+    if (r, g, b, a) == (255, 255, 255, 255) && params.opacity >= 1.0 {
+        return None;
+    }
+    let alpha = (a as f32 / 255.0) * params.opacity.clamp(0.0, 1.0);
+    let factor = |c: u8| (c as f32 * alpha).round() as u16;
+    Some([factor(r), factor(g), factor(b), factor(255)])
+}
+
+fn modulate(from: &[u8], mul: [u16; 4]) -> [u8; 4] {
+    [
+        div255(from[0] as u16 * mul[0]) as u8,
+        div255(from[1] as u16 * mul[1]) as u8,
+        div255(from[2] as u16 * mul[2]) as u8,
+        div255(from[3] as u16 * mul[3]) as u8,
+    ]
+}
+
+// x / 255, rounded to nearest, for any x up to 255 * 255 (i.e. the product of two channels).
+// Only adds and shifts, so it vectorizes.
+#[inline(always)]
+fn div255(x: u16) -> u16 {
+    let x = x + 128;
+    (x + (x >> 8)) >> 8
+}
+
+// Composite over, assume premultiplied rgba8888.
+// Alpha works out the same as the colors: what's there shows through whatever the source doesn't cover.
+#[inline(always)]
+fn over(to: &mut [u8], from: &[u8]) {
+    let uncovered = 255 - from[3] as u16;
+    for i in 0..4 {
+        to[i] = (from[i] as u16 + div255(to[i] as u16 * uncovered)).min(255) as u8;
+    }
+}
+
+// How many pixels over_row looks at together
+const OVER_BLOCK: usize = 16;
+
+// "Over" for a whole row of premultiplied rgba8888 pixels, the most common thing we draw.
+// Sprites are mostly either empty or solid, so blocks of pixels that are all transparent get skipped
+// and blocks that are all opaque are just copied. The rest go through branch-free integer math
+// that the compiler can turn into SIMD.
+fn over_row(to: &mut [u8], from: &[u8]) {
+    for (to, from) in to
+        .chunks_mut(OVER_BLOCK * 4)
+        .zip(from.chunks(OVER_BLOCK * 4))
+    {
+        let alphas = from.iter().skip(3).step_by(4);
+        let (mut any, mut all) = (0, 255);
+        for &a in alphas {
+            any |= a;
+            all &= a;
+        }
+        if any == 0 {
+            continue;
+        }
+        if all == 255 {
+            to.copy_from_slice(from);
+            continue;
+        }
+        match (
+            <&mut [u8; OVER_BLOCK * 4]>::try_from(&mut *to),
+            <&[u8; OVER_BLOCK * 4]>::try_from(from),
+        ) {
+            (Ok(to), Ok(from)) => over_block(to, from),
+            _ => {
+                for (to, from) in to.chunks_exact_mut(4).zip(from.chunks_exact(4)) {
+                    over(to, from);
                 }
-                to[3] = ((fa + ta * (1.0 - fa)) * 255.0).round() as u8;
             }
         }
     }
 }
+
+// The same math as `over`, on a fixed number of pixels at once so there are no bounds checks
+// and every channel gets the same treatment
+#[inline(always)]
+fn over_block(to: &mut [u8; OVER_BLOCK * 4], from: &[u8; OVER_BLOCK * 4]) {
+    for i in 0..OVER_BLOCK * 4 {
+        let uncovered = 255 - from[i | 3] as u16;
+        to[i] = (from[i] as u16 + div255(to[i] as u16 * uncovered)).min(255) as u8;
+    }
+}
+
+// The rest of the blend modes, all on premultiplied rgba8888 too.
+// They share the "over" alpha, except additive which just adds everything up.
+fn additive(to: &mut [u8], from: &[u8]) {
+    for i in 0..4 {
+        to[i] = to[i].saturating_add(from[i]);
+    }
+}
+
+fn multiply(to: &mut [u8], from: &[u8]) {
+    let (ta, fa) = (to[3] as u32, from[3] as u32);
+    for i in 0..3 {
+        let (t, f) = (to[i] as u32, from[i] as u32);
+        // Where both are opaque this is just t * f; elsewhere each side shows through the other's transparency
+        let sum = t * f + f * (255 - ta) + t * (255 - fa);
+        to[i] = ((sum + 127) / 255).min(255) as u8;
+    }
+    to[3] = (fa + div255((ta * (255 - fa)) as u16) as u32) as u8;
+}
+
+fn screen(to: &mut [u8], from: &[u8]) {
+    let (ta, fa) = (to[3] as u16, from[3] as u16);
+    for i in 0..3 {
+        let (t, f) = (to[i] as u16, from[i] as u16);
+        to[i] = (t + f - div255(t * f)).min(255) as u8;
+    }
+    to[3] = (fa + div255(ta * (255 - fa))) as u8;
+}
+
+fn copy(to: &mut [u8], from: &[u8]) {
+    to.copy_from_slice(from);
+}
@@ -1,5 +1,11 @@
 use crate::animation::Animation;
+use crate::assets::Assets;
+use crate::error::Result;
+use crate::font::BitmapFont;
+use crate::particles::{Emitter, Shape};
+use crate::recolor::Recolor;
 use crate::sprite::Sprite;
+use crate::terrain::Terrain;
 use crate::texture::Texture;
 use crate::types::*;
 use rand::Rng;
@@ -7,8 +13,8 @@ use std::path::Path;
 use std::rc::Rc;
 use crate::tile::*;
 
-pub fn make_player() -> Sprite {
-    let slug = Rc::new(Texture::with_file(Path::new("slug/slug.png")));
+pub fn make_player(assets: &mut Assets) -> Result<Sprite> {
+    let slug = assets.texture(Path::new("slug/slug.png"))?;
     Sprite::new(
         &slug,
         Animation::new(vec![
@@ -26,18 +32,6 @@ pub fn make_player() -> Sprite {
                         w: 48,
                         h: 48,
                     },
-                    Rect {
-                        x: 0,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                    Rect {
-                        x: 48,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
                 ],
                 current_index: 0,
                 start_time: 0,
@@ -46,7 +40,7 @@ pub fn make_player() -> Sprite {
             AnimationState {
                 frames: vec![
                     Rect {
-                        x: 0, // Left facing slug
+                        x: 0, // Left facing slug, set flip_x to face right
                         y: 0,
                         w: 48,
                         h: 48,
@@ -62,26 +56,6 @@ pub fn make_player() -> Sprite {
                 start_time: 0,
                 repeat: true,
             },
-            AnimationState {
-                frames: vec![
-                    Rect {
-                        // Right facing slug
-                        x: 0,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                    Rect {
-                        x: 48,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                ],
-                current_index: 0,
-                start_time: 0,
-                repeat: true,
-            },
         ]),
         Vec2i(10, 50),
         vec![Rect {
@@ -96,8 +70,22 @@ pub fn make_player() -> Sprite {
     )
 }
 
-pub fn make_enemy() -> Sprite {
-    let enemy = Rc::new(Texture::with_file(Path::new("slug/enemy.png")));
+// The body color of the player slug
+const SLUG_GREEN: Rgba = Rgba(107, 242, 12, 255);
+
+/// The player slug with its body in another color, to tell players apart in a versus mode
+pub fn make_team_player(assets: &mut Assets, color: Rgba) -> Result<Sprite> {
+    let key = format!("slug/slug.png#{:02x}{:02x}{:02x}", color.0, color.1, color.2);
+    let recolor = Recolor::Palette(vec![(SLUG_GREEN, color)]);
+    let slug = assets.recolored(Path::new("slug/slug.png"), &key, &recolor)?;
+    let mut player = make_player(assets)?;
+    player.image = slug;
+    Ok(player)
+}
+
+// The enemy could almost be make_team_player in red, but it has angry eyebrows the player doesn't
+pub fn make_enemy(assets: &mut Assets) -> Result<Sprite> {
+    let enemy = assets.texture(Path::new("slug/enemy.png"))?;
     Sprite::new(
         &enemy,
         Animation::new(vec![
@@ -115,18 +103,6 @@ pub fn make_enemy() -> Sprite {
                         w: 48,
                         h: 48,
                     },
-                    Rect {
-                        x: 0,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                    Rect {
-                        x: 48,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
                 ],
                 current_index: 0,
                 start_time: 0,
@@ -135,7 +111,7 @@ pub fn make_enemy() -> Sprite {
             AnimationState {
                 frames: vec![
                     Rect {
-                        x: 0, // Left facing slug
+                        x: 0, // Left facing slug, set flip_x to face right
                         y: 0,
                         w: 48,
                         h: 48,
@@ -151,26 +127,6 @@ pub fn make_enemy() -> Sprite {
                 start_time: 0,
                 repeat: true,
             },
-            AnimationState {
-                frames: vec![
-                    Rect {
-                        // Right facing slug
-                        x: 0,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                    Rect {
-                        x: 48,
-                        y: 48,
-                        w: 48,
-                        h: 48,
-                    },
-                ],
-                current_index: 0,
-                start_time: 0,
-                repeat: true,
-            },
         ]),
         Vec2i(100, 160),
         vec![Rect {
@@ -185,27 +141,68 @@ pub fn make_enemy() -> Sprite {
     )
 }
 
-pub fn make_core() -> Vec<Sprite> {
+/// A wall of mud down the right of the level, made of wall tiles tinted brown, that the slug can dig through
+pub fn make_mud(assets: &mut Assets) -> Result<Terrain> {
+    let wall = assets.texture(Path::new("slug/wall.png"))?;
+    let tile = Rect {
+        x: 0,
+        y: 0,
+        w: 48,
+        h: 48,
+    };
+    let brown = DrawParams {
+        tint: Rgba(150, 100, 60, 255),
+        ..DrawParams::default()
+    };
+    // Five tiles tall, from the fourth row down to the eighth
+    let mut texture = Texture::blank(48, 240);
+    let mut canvas = texture.canvas(Vec2i(0, 0));
+    for y in (0..240).step_by(48) {
+        canvas.bitblt_with(&wall, tile, Vec2i(0, y), brown);
+    }
+    Ok(Terrain::new(texture, Vec2i(384, 144)))
+}
+
+/// Blobs of slime left behind the slug; keep it under the slug and turn `rate` up while it moves
+pub fn make_slime_trail() -> Emitter {
+    let mut slime = Emitter::new(Vec2i(0, 0), Shape::Circle);
+    slime.radius = 6.0;
+    slime.speed = 0.0..0.3;
+    slime.drag = 0.9;
+    slime.lifetime = 40..70;
+    let Rgba(r, g, b, _) = SLUG_GREEN;
+    slime.color = (Rgba(r, g, b, 200), Rgba(r, g, b, 0));
+    slime.size = (5.0, 2.0);
+    slime
+}
+
+pub fn make_core(assets: &mut Assets) -> Result<Vec<Sprite>> {
     let mut core: Vec<Sprite> = vec![];
-    core.push(make_player());
-    core.push(make_enemy());
-    return core;
+    core.push(make_player(assets)?);
+    core.push(make_enemy(assets)?);
+    return Ok(core);
+}
+
+pub fn make_font(assets: &mut Assets) -> Result<BitmapFont> {
+    BitmapFont::load(
+        assets,
+        Path::new("fonts/font5x7.png"),
+        Path::new("fonts/font5x7.fnt"),
+    )
 }
 
-pub fn make_menus() ->  Vec<Rc<Texture>> {
-    let menu_1 = Rc::new(Texture::with_file(Path::new("slug/screens/play.png")));
-    let menu_2 = Rc::new(Texture::with_file(Path::new("slug/screens/load.png")));
-    let menu_3 = Rc::new(Texture::with_file(Path::new("slug/screens/tut.png")));
-    let help = Rc::new(Texture::with_file(Path::new("slug/screens/help.png")));
-    let game_over = Rc::new(Texture::with_file(Path::new(
-        "slug/screens/game_over.png",
-    )));
-    let win = Rc::new(Texture::with_file(Path::new("slug/screens/win.png")));
-    return vec![menu_1, menu_2, menu_3, help, win, game_over];
+pub fn make_menus(assets: &mut Assets) -> Result<Vec<Rc<Texture>>> {
+    let menu_1 = assets.texture(Path::new("slug/screens/play.png"))?;
+    let menu_2 = assets.texture(Path::new("slug/screens/load.png"))?;
+    let menu_3 = assets.texture(Path::new("slug/screens/tut.png"))?;
+    let help = assets.texture(Path::new("slug/screens/help.png"))?;
+    let game_over = assets.texture(Path::new("slug/screens/game_over.png"))?;
+    let win = assets.texture(Path::new("slug/screens/win.png"))?;
+    return Ok(vec![menu_1, menu_2, menu_3, help, win, game_over]);
 }
 
-pub fn reset_tiles() -> Tilemap {
-    let mut tiles = Rc::new(Texture::with_file(Path::new("slug/slug_tiles.png")));
+pub fn reset_tiles(assets: &mut Assets) -> Result<Tilemap> {
+    let mut tiles = assets.texture(Path::new("slug/slug_tiles.png"))?;
 
     //Create the tiles
     let first_tile = Tile { solid: true };
@@ -214,12 +211,12 @@ pub fn reset_tiles() -> Tilemap {
     let fourth_tile = Tile { solid: true };
   
     let mut map = vec![1,1,1,1,1,1,0,1,1,1,1,1,1,1,1,3,1,1,1,1,1,1,1,1,1, 1,1,1,1,1,1,0,1,1,1,1,1,1,1,1,3,1,1,1,1,1,1,1,1,1, 1,1,1,1,1,1,0,1,1,1,1,1,1,1,1,3,1,1,1,1,1,1,1,1,1, 1,1,1,1,1,1,0,1,1,1,1,1,1,1,1,3,1,1,1,1,1,1,1,1,1,];
-    let tile_set = Rc::new(Tileset{tiles : vec![first_tile, second_tile, third_tile,fourth_tile], texture:tiles});
+    let tile_set = Rc::new(Tileset::new(vec![first_tile, second_tile, third_tile,fourth_tile], &tiles)?);
     let tilemap= Tilemap::new(
         Vec2i(0,0),
         ((10) ,(10)),
         &tile_set,
         map,
     );
-    return tilemap
+    return Ok(tilemap);
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use crate::animation::Animation;
+use crate::batch::Blit;
+use crate::error::Result;
 use crate::texture::Texture;
-use crate::types::{Rect, Vec2i};
+use crate::types::{BlendMode, DrawParams, Rect, Rgba, Transform, Vec2i};
 use std::rc::Rc;
 
 pub struct Sprite {
@@ -12,9 +14,22 @@ pub struct Sprite {
     pub exploded: bool,
     pub is_explodable: bool,
     pub is_obstacle: bool,
+    // Mirror the current frame when drawing, so one set of frames can face either way
+    pub flip_x: bool,
+    pub flip_y: bool,
+    // Clockwise rotation in radians and scale, both around the middle of the frame
+    pub rotation: f32,
+    pub scale: (f32, f32),
+    // Color to multiply the sprite by (white for none), how opaque to draw it and how to blend it
+    pub tint: Rgba,
+    pub opacity: f32,
+    pub blend: BlendMode,
+    // Position is in screen coordinates rather than the world's, so the sprite ignores the camera (e.g. HUD elements)
+    pub hud: bool,
 }
 
 impl Sprite {
+    /// Make a sprite, failing if any of its animation frames don't fit in `image`
     pub fn new(
         image: &Rc<Texture>,
         animation: Animation,
@@ -23,12 +38,13 @@ impl Sprite {
         exploded: bool,
         is_explodable: bool,
         is_obstacle: bool,
-    ) -> Self {
+    ) -> Result<Self> {
+        animation.validate(image)?;
         for mut rect in &mut hit_boxes {
             rect.x += x;
             rect.y += y;
         }
-        Self {
+        Ok(Self {
             image: Rc::clone(image),
             animation,
             position: Vec2i(x, y),
@@ -37,6 +53,43 @@ impl Sprite {
             exploded: exploded,
             is_explodable,
             is_obstacle,
+            flip_x: false,
+            flip_y: false,
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+            tint: Rgba(255, 255, 255, 255),
+            opacity: 1.0,
+            blend: BlendMode::Over,
+            hud: false,
+        })
+    }
+
+    /// How to draw the sprite's current frame, e.g. to put it in a `Batch`
+    pub fn blit(&self) -> Blit<'_> {
+        let frame = self.animation.states[self.animation.index].frames
+            [self.animation.states[self.animation.index].current_index];
+        let transform = if self.rotation == 0.0 && self.scale == (1.0, 1.0) {
+            None
+        } else {
+            Some(Transform {
+                rotation: self.rotation,
+                scale: self.scale,
+                pivot: (frame.w as f32 / 2.0, frame.h as f32 / 2.0),
+            })
+        };
+        Blit {
+            texture: &self.image,
+            from: frame,
+            to: self.position,
+            transform,
+            params: DrawParams {
+                flip_x: self.flip_x,
+                flip_y: self.flip_y,
+                tint: self.tint,
+                opacity: self.opacity,
+                blend: self.blend,
+            },
+            hud: self.hud,
         }
     }
 
@@ -55,12 +108,12 @@ impl Sprite {
 }
 
 pub trait DrawSpriteExt {
-    fn draw_sprite(&mut self, s: &mut Sprite);
+    fn draw_sprite(&mut self, s: &Sprite);
 }
 
 use crate::screen::Screen;
 impl<'fb> DrawSpriteExt for Screen<'fb> {
-    fn draw_sprite(&mut self, s: &mut Sprite) {
+    fn draw_sprite(&mut self, s: &Sprite) {
         // This works because we're only using a public method of Screen here,
         // and the private fields of sprite are visible inside this module
         // if s.exploded {
@@ -72,11 +125,6 @@ impl<'fb> DrawSpriteExt for Screen<'fb> {
         //     }
 
         // }
-        self.bitblt(
-            &s.image,
-            s.animation.states[s.animation.index].frames
-                [s.animation.states[s.animation.index].current_index],
-            s.position,
-        );
+        s.blit().draw(self);
     }
 }
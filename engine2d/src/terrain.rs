@@ -0,0 +1,172 @@
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::*;
+
+/// Pixels at least this opaque are solid
+pub const SOLID_ALPHA: u8 = 128;
+
+/// Scenery that can be blown apart a pixel at a time: a texture to draw, along with a mask saying
+/// which of its pixels are solid enough to run into. Carving holes changes both.
+pub struct Terrain {
+    /// Where the top-left of the texture is in the world
+    pub position: Vec2i,
+    texture: Texture,
+    // Whether each pixel is solid, row by row
+    mask: Vec<bool>,
+}
+
+impl Terrain {
+    /// Terrain that looks like `texture` and is solid wherever that's at least `SOLID_ALPHA` opaque
+    pub fn new(texture: Texture, position: Vec2i) -> Self {
+        let mask = texture
+            .buffer()
+            .chunks_exact(texture.depth())
+            .map(|px| px[3] >= SOLID_ALPHA)
+            .collect();
+        Self {
+            position,
+            texture,
+            mask,
+        }
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.texture.size()
+    }
+
+    /// The part of the world the terrain takes up, holes and all
+    pub fn bounds(&self) -> Rect {
+        let (w, h) = self.size();
+        Rect {
+            x: self.position.0,
+            y: self.position.1,
+            w: w as u16,
+            h: h as u16,
+        }
+    }
+
+    /// How many solid pixels are left, e.g. to tell when something's been mostly blown away
+    pub fn solid_count(&self) -> usize {
+        self.mask.iter().filter(|&&solid| solid).count()
+    }
+
+    /// Whether the pixel at a point in the world is solid
+    pub fn is_solid(&self, Vec2i(x, y): Vec2i) -> bool {
+        let (w, h) = self.size();
+        let (x, y) = (x - self.position.0, y - self.position.1);
+        x >= 0
+            && y >= 0
+            && (x as usize) < w
+            && (y as usize) < h
+            && self.mask[y as usize * w + x as usize]
+    }
+
+    /// Whether anything solid is inside `area` (in world coordinates), e.g. a sprite's hit box
+    pub fn overlaps(&self, area: Rect) -> bool {
+        self.first_solid(area).is_some()
+    }
+
+    /// The leftmost solid point inside `area` (the topmost of those, if there's a tie), in world coordinates.
+    /// That's where something flying in from the left hits first.
+    pub fn first_solid(&self, area: Rect) -> Option<Vec2i> {
+        let area = self.bounds().intersection(area);
+        let w = self.size().0;
+        for x in area.x..area.x + area.w as i32 {
+            for y in area.y..area.y + area.h as i32 {
+                let (tx, ty) = (
+                    (x - self.position.0) as usize,
+                    (y - self.position.1) as usize,
+                );
+                if self.mask[ty * w + tx] {
+                    return Some(Vec2i(x, y));
+                }
+            }
+        }
+        None
+    }
+
+    /// Blow away every pixel within `radius` of `center` (in world coordinates), the same ones
+    /// `Screen::fill_circle` would fill. Returns how many solid pixels went.
+    pub fn carve_circle(&mut self, center: Vec2i, radius: u16) -> usize {
+        let (w, h) = self.size();
+        if self.mask.is_empty() {
+            return 0;
+        }
+        let (cx, cy) = (
+            (center.0 - self.position.0) as i64,
+            (center.1 - self.position.1) as i64,
+        );
+        // Squaring a big radius doesn't fit in an i32, and only the rows and columns inside the texture matter
+        let r = radius as i64;
+        let pixels = self.texture.buffer_mut();
+        let mut carved = 0;
+        for dy in (-r).max(-cy)..=r.min(h as i64 - 1 - cy) {
+            let half = ((r * r - dy * dy) as f64).sqrt() as i64;
+            for x in (cx - half).max(0)..=(cx + half).min(w as i64 - 1) {
+                carved += erase(pixels, &mut self.mask, w, x as i32, (cy + dy) as i32, 255);
+            }
+        }
+        carved
+    }
+
+    /// Blow away pixels wherever `mask` is opaque, with its top-left at `at` (in world coordinates).
+    /// Partly transparent parts of the mask only fade the terrain a bit, which leaves it solid
+    /// for as long as it's still at least `SOLID_ALPHA` opaque. Returns how many solid pixels went.
+    pub fn carve_mask(&mut self, mask: &Texture, at: Vec2i) -> usize {
+        let mw = mask.size().0;
+        let (ox, oy) = (at.0 - self.position.0, at.1 - self.position.1);
+        let w = self.size().0;
+        if self.mask.is_empty() {
+            return 0;
+        }
+        let pixels = self.texture.buffer_mut();
+        let mut carved = 0;
+        for (i, px) in mask.buffer().chunks_exact(mask.depth()).enumerate() {
+            if px[3] > 0 {
+                let (x, y) = ((i % mw) as i32, (i / mw) as i32);
+                carved += erase(pixels, &mut self.mask, w, ox + x, oy + y, px[3]);
+            }
+        }
+        carved
+    }
+
+    pub fn draw(&self, screen: &mut Screen) {
+        self.draw_with(screen, DrawParams::default());
+    }
+
+    pub fn draw_with(&self, screen: &mut Screen, params: DrawParams) {
+        let (w, h) = self.size();
+        let whole = Rect {
+            x: 0,
+            y: 0,
+            w: w as u16,
+            h: h as u16,
+        };
+        screen.bitblt_with(&self.texture, whole, self.position, params);
+    }
+}
+
+// Take `amount` (out of 255) of the pixel at (x, y) away from the `w` pixel wide `pixels` and its `mask`,
+// returning 1 if that leaves a solid pixel not solid any more. Carving takes the texture's pixels just once,
+// since each time counts as a change to it. There has to be at least one pixel.
+fn erase(pixels: &mut [u8], mask: &mut [bool], w: usize, x: i32, y: i32, amount: u8) -> usize {
+    let h = mask.len() / w;
+    if x < 0 || y < 0 || x as usize >= w || y as usize >= h {
+        return 0;
+    }
+    let i = y as usize * w + x as usize;
+    let depth = pixels.len() / mask.len();
+    let px = &mut pixels[i * depth..(i + 1) * depth];
+    // It's premultiplied, so fading it out scales every channel alike
+    let keep = 255 - amount as u16;
+    for c in px.iter_mut() {
+        *c = ((*c as u16 * keep + 127) / 255) as u8;
+    }
+    let was = mask[i];
+    mask[i] = px[3] >= SOLID_ALPHA;
+    (was && !mask[i]) as usize
+}
@@ -1,11 +1,24 @@
+use crate::error::{Error, Result};
+use crate::recolor::Recolor;
+use crate::screen::Screen;
+use crate::types::{Rect, Vec2i};
 use image::{self, RgbaImage};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub struct Texture {
     image: Vec<u8>,
     pub width: usize,
     pub height: usize,
     depth: usize,
+    id: usize,
+}
+
+// Every texture gets its own id, so draws of different textures can be told apart
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 #[allow(dead_code)]
@@ -14,8 +27,18 @@ enum AlphaChannel {
     Last,
 }
 impl Texture {
-    pub fn with_file(path: &Path) -> Self {
-        Self::new(image::open(path).expect("Couldn't load image").into_rgba8())
+    /// Load and decode an image file; the error says which file it was
+    pub fn with_file(path: &Path) -> Result<Self> {
+        let image = image::open(path).map_err(|source| Error::Image {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self::new(image.into_rgba8()))
+    }
+    /// Decode an image that's already in memory (e.g. from `include_bytes!`), guessing its format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let image = image::load_from_memory(bytes).map_err(Error::Decode)?;
+        Ok(Self::new(image.into_rgba8()))
     }
     pub fn new(image: RgbaImage) -> Self {
         let (width, height) = image.dimensions();
@@ -26,8 +49,66 @@ impl Texture {
             height: height as usize,
             depth: 4,
             image,
+            id: next_id(),
+        }
+    }
+    /// Wrap pixels that are already premultiplied RGBA, e.g. a copy of a framebuffer
+    pub fn from_premultiplied(width: usize, height: usize, image: Vec<u8>) -> Self {
+        assert_eq!(
+            image.len(),
+            width * height * 4,
+            "Texture is the wrong size!"
+        );
+        Self {
+            image,
+            width,
+            height,
+            depth: 4,
+            id: next_id(),
         }
     }
+    /// A fully transparent texture, e.g. to draw into with `canvas`
+    pub fn blank(width: usize, height: usize) -> Self {
+        Self::from_premultiplied(width, height, vec![0; width * height * 4])
+    }
+    /// Draw into the texture just like into the screen, as seen by a camera at `position`.
+    /// Whatever gets drawn can be blitted from the texture afterwards like from any other.
+    pub fn canvas(&mut self, position: Vec2i) -> Screen<'_> {
+        self.id = next_id();
+        Screen::wrap(
+            &mut self.image,
+            self.width,
+            self.height,
+            self.depth,
+            position,
+        )
+    }
+    /// The (premultiplied) pixels, to change them directly
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        self.id = next_id();
+        &mut self.image
+    }
+    /// A copy of this texture with its colors changed, still premultiplied.
+    /// This touches every pixel, so do it once (e.g. through `Assets::recolored`) rather than every frame.
+    pub fn recolored(&self, recolor: &Recolor) -> Texture {
+        let mut image = self.image.clone();
+        for px in image.chunks_exact_mut(self.depth) {
+            let out = recolor.apply([px[0], px[1], px[2], px[3]]);
+            px.copy_from_slice(&out);
+        }
+        Self {
+            image,
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            id: next_id(),
+        }
+    }
+    /// Tells this texture apart from every other one, e.g. so `DirtyRects` can notice a sprite changing textures.
+    /// It changes whenever the texture could have been drawn into, too.
+    pub fn id(&self) -> usize {
+        self.id
+    }
     pub fn depth(&self) -> usize {
         self.depth
     }
@@ -40,12 +121,13 @@ impl Texture {
     pub fn buffer(&self) -> &[u8] {
         &self.image
     }
-    // pub fn valid_frame(&self, frame: Rect) -> bool {
-    //     0 <= frame.x
-    //         && (frame.x + frame.w as i32) <= (self.width as i32)
-    //         && 0 <= frame.y
-    //         && (frame.y + frame.h as i32) <= (self.height as i32)
-    // }
+    /// Does `frame` lie entirely inside this texture?
+    pub fn valid_frame(&self, frame: Rect) -> bool {
+        0 <= frame.x
+            && (frame.x + frame.w as i32) <= (self.width as i32)
+            && 0 <= frame.y
+            && (frame.y + frame.h as i32) <= (self.height as i32)
+    }
 }
 
 fn premultiply(img: &mut [u8], depth: usize, alpha: AlphaChannel) {
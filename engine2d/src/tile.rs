@@ -1,3 +1,5 @@
+use crate::batch::{Batch, Blit};
+use crate::error::{Error, Result};
 use crate::screen::Screen;
 use crate::texture::Texture;
 use crate::types::*;
@@ -17,6 +19,8 @@ pub struct Tileset {
     pub texture: Rc<Texture>,
     // In this design, each tileset is a distinct image.
     // Maybe not always the best choice if there aren't many tiles in a tileset!
+    // So optionally each tile can say where it is instead, e.g. somewhere in an `Atlas`.
+    rects: Vec<Rect>,
 }
 /// Indices into a Tileset
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -30,15 +34,46 @@ impl std::ops::Index<TileID> for Tileset {
     }
 }
 impl Tileset {
-    /// Create a new tileset
-    pub fn new(tiles: Vec<Tile>, texture: &Rc<Texture>) -> Self {
-        Self {
+    /// Create a new tileset, failing if `texture` doesn't have a grid cell for every tile
+    pub fn new(tiles: Vec<Tile>, texture: &Rc<Texture>) -> Result<Self> {
+        let tileset = Self {
+            tiles,
+            texture: Rc::clone(texture),
+            rects: vec![],
+        };
+        tileset.validate()?;
+        Ok(tileset)
+    }
+    /// Create a tileset whose tiles sit at arbitrary spots in `texture`, one rect per tile.
+    /// The rects should still be `TILE_SZ` square, since that's how big tiles get drawn.
+    pub fn with_rects(tiles: Vec<Tile>, texture: &Rc<Texture>, rects: Vec<Rect>) -> Result<Self> {
+        assert_eq!(tiles.len(), rects.len(), "Every tile needs a rect");
+        let tileset = Self {
             tiles,
             texture: Rc::clone(texture),
+            rects,
+        };
+        tileset.validate()?;
+        Ok(tileset)
+    }
+    fn validate(&self) -> Result<()> {
+        for tile in 0..self.tiles.len() {
+            let rect = self.get_rect(TileID(tile));
+            if !self.texture.valid_frame(rect) {
+                return Err(Error::BadTile {
+                    tile,
+                    rect,
+                    size: self.texture.size(),
+                });
+            }
         }
+        Ok(())
     }
     /// Get the frame rect for a tile ID
     fn get_rect(&self, id: TileID) -> Rect {
+        if !self.rects.is_empty() {
+            return self.rects[id.0];
+        }
         let idx = id.0;
         let (w, _h) = self.texture.size();
 
@@ -133,17 +168,55 @@ impl Tilemap {
     pub fn size(&self) -> (usize, usize) {
         self.dims
     }
+    /// The world-space pixels the map covers, e.g. for keeping a `Camera` on the map
+    pub fn bounds(&self) -> Rect {
+        Rect {
+            x: self.position.0,
+            y: self.position.1,
+            w: self.dims.0 as u16 * TILE_SZ,
+            h: self.dims.1 as u16 * TILE_SZ,
+        }
+    }
     pub fn tile_at(&self, posn: Vec2i) -> Tile {
         self.tileset[self.tile_id_at(posn)]
     }
 
+    /// Draw the whole map into a texture of its own, to be drawn at the map's position later on in one blit.
+    /// Only worth it for maps that aren't going to change.
+    pub fn bake(&self) -> Texture {
+        let Rect { x, y, w, h } = self.bounds();
+        let mut texture = Texture::blank(w as usize, h as usize);
+        self.draw(&mut texture.canvas(Vec2i(x, y)));
+        texture
+    }
+
     pub fn draw(&self, screen: &mut Screen) {
+        self.draw_with(screen, DrawParams::default());
+    }
+
+    /// Draw every visible tile with the same options, e.g. `BlendMode::Copy` for opaque tilesets
+    pub fn draw_with(&self, screen: &mut Screen, params: DrawParams) {
+        for blit in self.blits(screen.bounds(), params) {
+            blit.draw(screen);
+        }
+    }
+
+    /// Same as `draw_with`, but split over all the cores in horizontal bands. The picture comes out the same;
+    /// it's just quicker for big screens full of tiles.
+    pub fn draw_parallel(&self, screen: &mut Screen, params: DrawParams) {
+        let mut batch = Batch::new();
+        batch.extend(self.blits(screen.bounds(), params));
+        batch.draw_parallel(screen);
+    }
+
+    /// The blits that draw every tile visible in `view` (in world coordinates), top to bottom and left to right
+    pub fn blits(&self, view: Rect, params: DrawParams) -> impl Iterator<Item = Blit<'_>> {
         let Rect {
             x: sx,
             y: sy,
             w: sw,
             h: sh,
-        } = screen.bounds();
+        } = view;
         // We'll draw from the topmost/leftmost visible tile to the bottommost/rightmost visible tile.
         // The camera combined with out position and size tell us what's visible.
         // leftmost tile: get camera.x into our frame of reference, then divide down to tile units
@@ -164,17 +237,25 @@ impl Tilemap {
             .min(self.dims.1 as i32) as usize;
         // Now draw the tiles we need to draw where we need to draw them.
         // Note that we're zipping up the row index (y) with a slice of the map grid containing the necessary rows so we can avoid making a bounds check for each tile.
-        for (y, row) in (top..bot)
+        (top..bot)
             .zip(self.map[(top * self.dims.0)..(bot * self.dims.0)].chunks_exact(self.dims.0))
-        {
-            // We are in tile coordinates at this point so we'll need to translate back to pixel units and world coordinates to draw.
-            let ypx = (y * TILE_SZ as usize) as i32 + self.position.1;
-            // Here we can iterate through the column index and the relevant slice of the row in parallel
-            for (x, id) in (left..right).zip(row[left..right].iter()) {
-                let xpx = (x * TILE_SZ as usize) as i32 + self.position.0;
-                let frame = self.tileset.get_rect(*id);
-                screen.bitblt(&self.tileset.texture, frame, Vec2i(xpx, ypx));
-            }
-        }
+            .flat_map(move |(y, row)| {
+                // We are in tile coordinates at this point so we'll need to translate back to pixel units and world coordinates to draw.
+                let ypx = (y * TILE_SZ as usize) as i32 + self.position.1;
+                // Here we can iterate through the column index and the relevant slice of the row in parallel
+                (left..right)
+                    .zip(row[left..right].iter())
+                    .map(move |(x, id)| {
+                        let xpx = (x * TILE_SZ as usize) as i32 + self.position.0;
+                        Blit {
+                            texture: &self.tileset.texture,
+                            from: self.tileset.get_rect(*id),
+                            to: Vec2i(xpx, ypx),
+                            transform: None,
+                            params,
+                            hud: false,
+                        }
+                    })
+            })
     }
 }
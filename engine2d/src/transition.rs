@@ -0,0 +1,150 @@
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::*;
+
+/// How one scene gives way to the next
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Effect {
+    /// The old scene fades out to this color, then the new one fades in from it
+    Fade(Rgba),
+    /// The old scene fades away, showing the new one underneath
+    Crossfade,
+    /// The old scene is pulled away to the right, uncovering the new one from the left
+    Wipe,
+    /// The old scene shrinks away into a circle of this color, then the new one grows out of it
+    Iris(Rgba),
+}
+
+/// A transition between two scenes that plays out over a number of frames without holding anything up.
+/// Switch scenes as usual, then every frame:
+/// `capture` the old scene before drawing anything else, draw the new scene, `draw` the transition
+/// over it and `update` it once per simulation step until it's `done`.
+pub struct Transition {
+    pub effect: Effect,
+    /// How many frames it takes
+    pub duration: usize,
+    frame: usize,
+    // The last frame of the old scene, once it's been captured
+    old: Option<Texture>,
+}
+
+impl Transition {
+    pub fn new(effect: Effect, duration: usize) -> Self {
+        Self {
+            effect,
+            duration,
+            frame: 0,
+            old: None,
+        }
+    }
+
+    /// Hold on to what's on screen as the old scene; only the first call does anything.
+    /// The framebuffer still has the last frame in it until it's cleared, so that's a good time.
+    pub fn capture(&mut self, screen: &Screen) {
+        if self.old.is_none() {
+            self.old = Some(screen.capture());
+        }
+    }
+
+    /// Move on by a frame
+    pub fn update(&mut self) {
+        self.frame = (self.frame + 1).min(self.duration);
+    }
+
+    /// How far along it is, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.duration == 0 {
+            1.0
+        } else {
+            self.frame as f32 / self.duration as f32
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.frame >= self.duration
+    }
+
+    /// Draw the transition over the new scene, which should already be on screen
+    pub fn draw(&self, screen: &mut Screen) {
+        let p = self.progress();
+        let view = screen.bounds();
+        let whole = Rect { x: 0, y: 0, ..view };
+        // The old scene is in screen coordinates, just like the HUD
+        screen.hud(|screen| match self.effect {
+            Effect::Fade(col) => {
+                // Out to the color over the first half, in from it over the second
+                let amount = if p < 0.5 { p * 2.0 } else { 2.0 - p * 2.0 };
+                if p < 0.5 {
+                    self.draw_old(screen, whole, Vec2i(0, 0), 1.0);
+                }
+                let Rgba(r, g, b, a) = col;
+                let a = (a as f32 * amount).round() as u8;
+                screen.fill_rect(whole, Rgba(r, g, b, a));
+            }
+            Effect::Crossfade => self.draw_old(screen, whole, Vec2i(0, 0), 1.0 - p),
+            Effect::Wipe => {
+                // What's left of the old scene is its left part, moved over by however far it's gone
+                let cut = (p * view.w as f32).round() as i32;
+                let rest = Rect {
+                    w: (view.w as i32 - cut).max(0) as u16,
+                    ..whole
+                };
+                self.draw_old(screen, rest, Vec2i(cut, 0), 1.0);
+            }
+            Effect::Iris(col) => {
+                // Half the diagonal is just enough to show the whole screen
+                let (cx, cy) = (view.w as f32 / 2.0, view.h as f32 / 2.0);
+                let full = (cx * cx + cy * cy).sqrt();
+                let radius = if p < 0.5 {
+                    self.draw_old(screen, whole, Vec2i(0, 0), 1.0);
+                    full * (1.0 - p * 2.0)
+                } else {
+                    full * (p * 2.0 - 1.0)
+                };
+                // Cover each row outside of the circle
+                for y in 0..view.h as i32 {
+                    let dy = y as f32 + 0.5 - cy;
+                    let (left, right) = if dy.abs() >= radius {
+                        (view.w as i32, view.w as i32)
+                    } else {
+                        let half = (radius * radius - dy * dy).sqrt();
+                        ((cx - half).round() as i32, (cx + half).round() as i32)
+                    };
+                    let row = Rect { y, h: 1, ..whole };
+                    screen.fill_rect(
+                        Rect {
+                            w: left.max(0) as u16,
+                            ..row
+                        },
+                        col,
+                    );
+                    screen.fill_rect(
+                        Rect {
+                            x: right,
+                            w: (view.w as i32 - right).max(0) as u16,
+                            ..row
+                        },
+                        col,
+                    );
+                }
+            }
+        });
+    }
+
+    // Put the part `area` of the captured old scene on screen at `to`
+    fn draw_old(&self, screen: &mut Screen, area: Rect, to: Vec2i, opacity: f32) {
+        if let Some(old) = &self.old {
+            let blend = if opacity >= 1.0 {
+                BlendMode::Copy
+            } else {
+                BlendMode::Over
+            };
+            let params = DrawParams {
+                opacity,
+                blend,
+                ..DrawParams::default()
+            };
+            screen.bitblt_with(old, area, to, params);
+        }
+    }
+}
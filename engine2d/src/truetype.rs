@@ -0,0 +1,173 @@
+use crate::error::{Error, Result};
+use crate::font::{BitmapFont, Glyph, TextStyle};
+use crate::screen::Screen;
+use crate::texture::Texture;
+use crate::types::{Rect, Vec2i};
+use fontdue::{Font, FontSettings};
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+/// An outline font, loaded from a TTF or OTF file. Turn it into pixels with a `GlyphCache`.
+pub struct TrueTypeFont {
+    font: Font,
+}
+
+impl TrueTypeFont {
+    pub fn with_file(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let font = Font::from_bytes(bytes, FontSettings::default()).map_err(|message| {
+            Error::TrueType {
+                path: path.to_path_buf(),
+                message,
+            }
+        })?;
+        Ok(Self { font })
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let font = Font::from_bytes(bytes, FontSettings::default()).map_err(Error::FontData)?;
+        Ok(Self { font })
+    }
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.font.has_glyph(c)
+    }
+}
+
+// How wide the atlas starts out; it mostly grows downwards
+const ATLAS_WIDTH: u32 = 256;
+// Empty space around each glyph so neighbours never bleed into each other
+const PADDING: u32 = 1;
+
+/// Glyphs of one font at one pixel size, rasterized the first time they're drawn
+/// and kept in an atlas texture that draws through `Screen` like any other.
+pub struct GlyphCache {
+    font: Rc<TrueTypeFont>,
+    px: f32,
+    ascent: i32,
+    line_height: i32,
+    // Straight alpha white glyphs; the texture gets remade from this whenever glyphs are added
+    atlas: RgbaImage,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    // Where the next glyph goes: the shelf we're filling and how tall it is so far
+    pen: (u32, u32),
+    shelf_height: u32,
+    bitmap: BitmapFont,
+}
+
+impl GlyphCache {
+    /// Make an (empty) cache of `font` rendered at `px` pixels per em
+    pub fn new(font: &Rc<TrueTypeFont>, px: f32) -> Self {
+        let metrics = font.font.horizontal_line_metrics(px);
+        let (ascent, line_height) = metrics.map_or((px.ceil() as i32, px.ceil() as i32), |m| {
+            (m.ascent.round() as i32, m.new_line_size.round() as i32)
+        });
+        let atlas = RgbaImage::new(ATLAS_WIDTH, 1);
+        let texture = Rc::new(Texture::new(atlas.clone()));
+        Self {
+            font: Rc::clone(font),
+            px,
+            ascent,
+            line_height,
+            atlas,
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            pen: (0, 0),
+            shelf_height: 0,
+            bitmap: BitmapFont::new(&texture, HashMap::new(), HashMap::new(), line_height),
+        }
+    }
+
+    /// Make sure every character of `text` is in the atlas
+    pub fn prepare(&mut self, text: &str) {
+        let mut added = false;
+        for c in text.chars() {
+            if c == '\n' || self.glyphs.contains_key(&c) {
+                continue;
+            }
+            self.rasterize(c);
+            added = true;
+        }
+        if added {
+            let texture = Rc::new(Texture::new(self.atlas.clone()));
+            self.bitmap = BitmapFont::new(
+                &texture,
+                self.glyphs.clone(),
+                self.kerning.clone(),
+                self.line_height,
+            );
+        }
+    }
+
+    /// The glyphs rasterized so far, as a regular bitmap font
+    pub fn font(&self) -> &BitmapFont {
+        &self.bitmap
+    }
+
+    /// Rasterize whatever's missing, then draw just like `BitmapFont::draw`
+    pub fn draw(&mut self, screen: &mut Screen, text: &str, position: Vec2i, style: TextStyle) {
+        self.prepare(text);
+        self.bitmap.draw(screen, text, position, style);
+    }
+
+    fn rasterize(&mut self, c: char) {
+        let (metrics, coverage) = self.font.font.rasterize(c, self.px);
+        let (w, h) = (metrics.width as u32, metrics.height as u32);
+        // Start a new shelf if this one's full, and grow the atlas if it's too short (or, for huge glyphs, too narrow)
+        if self.pen.0 + w + PADDING > self.atlas.width() {
+            self.pen = (0, self.pen.1 + self.shelf_height + PADDING);
+            self.shelf_height = 0;
+        }
+        let right = w + PADDING;
+        let bottom = self.pen.1 + h + PADDING;
+        if bottom > self.atlas.height() || right > self.atlas.width() {
+            let mut bigger = RgbaImage::new(
+                right.max(self.atlas.width()),
+                bottom.max(self.atlas.height() * 2),
+            );
+            for (x, y, px) in self.atlas.enumerate_pixels() {
+                bigger.put_pixel(x, y, *px);
+            }
+            self.atlas = bigger;
+        }
+        let (gx, gy) = self.pen;
+        for (i, a) in coverage.iter().enumerate() {
+            let (x, y) = (i as u32 % w, i as u32 / w);
+            self.atlas
+                .put_pixel(gx + x, gy + y, Rgba([255, 255, 255, *a]));
+        }
+        self.pen.0 += w + PADDING;
+        self.shelf_height = self.shelf_height.max(h);
+        // fontdue measures ymin up from the baseline to the bitmap's bottom; we measure down from the line's top
+        self.glyphs.insert(
+            c,
+            Glyph {
+                rect: Rect {
+                    x: gx as i32,
+                    y: gy as i32,
+                    w: w as u16,
+                    h: h as u16,
+                },
+                offset: Vec2i(metrics.xmin, self.ascent - metrics.ymin - h as i32),
+                advance: metrics.advance_width.round() as i32,
+            },
+        );
+        // Kern the new glyph against everything we've got so far (itself included)
+        let others: Vec<char> = self.glyphs.keys().copied().collect();
+        for other in others {
+            for &pair in &[(c, other), (other, c)] {
+                if let Some(k) = self.font.font.horizontal_kern(pair.0, pair.1, self.px) {
+                    let k = k.round() as i32;
+                    if k != 0 {
+                        self.kerning.insert(pair, k);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,44 @@ pub struct Rect {
     pub h: u16,
 }
 
+impl Rect {
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+    /// The part both rects cover; empty (and somewhere inside `self`) if they don't overlap
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w as i32).min(other.x + other.w as i32);
+        let y1 = (self.y + self.h as i32).min(other.y + other.h as i32);
+        Rect {
+            x: x0.min(self.x + self.w as i32),
+            y: y0.min(self.y + self.h as i32),
+            w: (x1 - x0).max(0) as u16,
+            h: (y1 - y0).max(0) as u16,
+        }
+    }
+    /// The smallest rect covering both; empty rects don't count
+    pub fn union(&self, other: Rect) -> Rect {
+        if self.is_empty() {
+            return other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = (self.x + self.w as i32).max(other.x + other.w as i32);
+        let y1 = (self.y + self.h as i32).max(other.y + other.h as i32);
+        Rect {
+            x: x0,
+            y: y0,
+            w: (x1 - x0) as u16,
+            h: (y1 - y0) as u16,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
 pub struct Vec2i(pub i32, pub i32);
 
@@ -19,3 +57,57 @@ pub struct AnimationState {
     pub start_time: usize,
     pub repeat: bool,
 }
+
+/// Extra options for a single draw, see `Screen::bitblt_with`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct DrawParams {
+    /// Mirror the source rect left-to-right
+    pub flip_x: bool,
+    /// Mirror the source rect top-to-bottom
+    pub flip_y: bool,
+    /// Multiply every source pixel by this (straight alpha) color; white leaves it alone
+    pub tint: Rgba,
+    /// Extra alpha multiplier for the whole draw, from 0.0 (invisible) to 1.0
+    pub opacity: f32,
+    /// How source pixels get combined with what's already on screen
+    pub blend: BlendMode,
+}
+
+impl Default for DrawParams {
+    fn default() -> Self {
+        Self {
+            flip_x: false,
+            flip_y: false,
+            tint: Rgba(255, 255, 255, 255),
+            opacity: 1.0,
+            blend: BlendMode::Over,
+        }
+    }
+}
+
+/// Ways of combining a (premultiplied) source pixel with the destination
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, Default)]
+pub enum BlendMode {
+    /// Regular "source over" alpha compositing
+    #[default]
+    Over,
+    /// Add the source onto the destination, good for glows and explosions
+    Additive,
+    /// Darken the destination by the source color
+    Multiply,
+    /// Lighten the destination by the inverse of the source color
+    Screen,
+    /// Replace the destination outright; fast, but only right for opaque sources
+    Copy,
+}
+
+/// Rotation and scaling for `Screen::bitblt_transformed`
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Transform {
+    /// Clockwise rotation in radians (y points down on screen)
+    pub rotation: f32,
+    /// Horizontal and vertical scale factors
+    pub scale: (f32, f32),
+    /// The point that stays put, relative to the top-left of the source rect
+    pub pivot: (f32, f32),
+}
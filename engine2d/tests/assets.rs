@@ -0,0 +1,108 @@
+use engine2d::assets::Assets;
+use engine2d::error::Error;
+use engine2d::llama_maker::{level_1, level_3};
+use engine2d::slug_maker::{make_core, reset_tiles};
+use engine2d::texture::Texture;
+use image::RgbaImage;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::rc::Rc;
+
+#[test]
+fn same_path_shares_one_texture() {
+    let mut assets = Assets::new();
+    let a = assets
+        .texture(Path::new("llama/sprites/asteroid.png"))
+        .unwrap();
+    let b = assets
+        .texture(Path::new("llama/sprites/asteroid.png"))
+        .unwrap();
+    assert!(Rc::ptr_eq(&a, &b));
+    assert_eq!(assets.len(), 1);
+}
+
+#[test]
+fn levels_decode_each_image_once() {
+    let mut assets = Assets::new();
+    let sprites = level_3(&mut assets).unwrap();
+    // Player, laser, battery, ship and the three kinds of asteroid
+    assert_eq!(assets.len(), 7);
+    let asteroids = &sprites[6..];
+    assert!(asteroids.len() > 7);
+    // Loading another level reuses everything we already have
+    level_1(&mut assets).unwrap();
+    assert_eq!(assets.len(), 7);
+}
+
+#[test]
+fn makers_share_a_cache_across_games() {
+    let mut assets = Assets::new();
+    let first = make_core(&mut assets).unwrap();
+    reset_tiles(&mut assets).unwrap();
+    let loaded = assets.len();
+    // Starting over (as the menu does) shouldn't load anything new
+    let second = make_core(&mut assets).unwrap();
+    reset_tiles(&mut assets).unwrap();
+    assert!(Rc::ptr_eq(&first[0].image, &second[0].image));
+    assert_eq!(assets.len(), loaded);
+}
+
+#[test]
+fn inserted_textures_are_found_by_key() {
+    let mut assets = Assets::new();
+    assert!(assets.is_empty());
+    assert!(assets.get("scratch").is_none());
+    let tex = assets.insert("scratch", Texture::new(RgbaImage::new(2, 2)));
+    assert!(Rc::ptr_eq(&tex, &assets.get("scratch").unwrap()));
+}
+
+#[test]
+fn missing_files_are_errors() {
+    let mut assets = Assets::new();
+    match assets.texture(Path::new("no/such/image.png")) {
+        Err(Error::Image { path, .. }) => assert_eq!(path, Path::new("no/such/image.png")),
+        Err(e) => panic!("wrong error: {}", e),
+        Ok(_) => panic!("loaded a file that doesn't exist"),
+    }
+    assert!(assets.is_empty());
+}
+
+#[test]
+fn textures_decode_from_bytes() {
+    let png = fs::read("llama/sprites/laser.png").unwrap();
+    let from_bytes = Texture::from_bytes(&png).unwrap();
+    let from_file = Texture::with_file(Path::new("llama/sprites/laser.png")).unwrap();
+    assert_eq!(from_bytes.size(), from_file.size());
+    assert_eq!(from_bytes.buffer(), from_file.buffer());
+    assert!(matches!(
+        Texture::from_bytes(b"not a png"),
+        Err(Error::Decode(_))
+    ));
+}
+
+#[test]
+fn error_message_names_the_file() {
+    match Texture::with_file(Path::new("llama/sprites/nope.png")) {
+        Err(e) => assert!(e.to_string().contains("llama/sprites/nope.png")),
+        Ok(_) => panic!("loaded a file that doesn't exist"),
+    }
+}
+
+// Both games load their assets relative to the working directory;
+// started from anywhere else they should say what's missing rather than panic
+#[test]
+fn games_report_missing_assets() {
+    let elsewhere = std::env::temp_dir();
+    for exe in &[env!("CARGO_BIN_EXE_llama"), env!("CARGO_BIN_EXE_slug")] {
+        let out = Command::new(exe)
+            .args(["--screenshot", "unused.png"])
+            .current_dir(&elsewhere)
+            .output()
+            .unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(stderr.contains("couldn't load image"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
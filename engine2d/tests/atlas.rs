@@ -0,0 +1,163 @@
+use engine2d::assets::Assets;
+use engine2d::atlas::{Atlas, AtlasBuilder};
+use engine2d::error::Error;
+use engine2d::llama_maker::level_1;
+use engine2d::offscreen::Offscreen;
+use engine2d::slug_maker::reset_tiles;
+use engine2d::sprite::DrawSpriteExt;
+use engine2d::tile::{Tile, Tilemap, Tileset, TILE_SZ};
+use engine2d::types::{Rect, Rgba, Vec2i};
+use image::RgbaImage;
+use std::path::Path;
+use std::rc::Rc;
+
+const LLAMA_SPRITES: &[&str] = &[
+    "llama/sprites/asteroid.png",
+    "llama/sprites/b_asteroid.png",
+    "llama/sprites/l_asteroid.png",
+    "llama/sprites/laser.png",
+    "llama/sprites/ship.png",
+    "llama/sprites/llama.png",
+    "llama/sprites/battery.png",
+];
+
+fn overlaps(a: Rect, b: Rect) -> bool {
+    a.x < b.x + b.w as i32
+        && b.x < a.x + a.w as i32
+        && a.y < b.y + b.h as i32
+        && b.y < a.y + a.h as i32
+}
+
+#[test]
+fn packed_images_stay_apart_and_inside() {
+    let mut builder = AtlasBuilder::new();
+    builder.padding = 2;
+    // A spread of awkward sizes, including some that only fit side by side
+    let sizes = [
+        (30, 10),
+        (7, 40),
+        (16, 16),
+        (16, 16),
+        (50, 3),
+        (1, 1),
+        (25, 25),
+        (9, 31),
+    ];
+    for (i, &(w, h)) in sizes.iter().enumerate() {
+        builder.add(&i.to_string(), RgbaImage::new(w, h));
+    }
+    let (image, rects) = builder.pack_image();
+    assert_eq!(rects.len(), sizes.len());
+    for (name, &r) in rects.iter() {
+        let (w, h) = sizes[name.parse::<usize>().unwrap()];
+        assert_eq!((r.w as u32, r.h as u32), (w, h));
+        assert!(r.x >= 0 && r.y >= 0);
+        assert!(r.x + r.w as i32 <= image.width() as i32);
+        assert!(r.y + r.h as i32 <= image.height() as i32);
+        for (other, &o) in rects.iter() {
+            if other != name {
+                let padded = Rect {
+                    w: r.w + 2,
+                    h: r.h + 2,
+                    ..r
+                };
+                assert!(!overlaps(padded, o), "{} is too close to {}", name, other);
+            }
+        }
+    }
+}
+
+#[test]
+fn atlas_pixels_match_the_originals() {
+    let mut builder = AtlasBuilder::new();
+    for path in LLAMA_SPRITES {
+        builder.add_file(path, Path::new(path)).unwrap();
+    }
+    let (image, rects) = builder.pack_image();
+    for path in LLAMA_SPRITES {
+        let original = image::open(path).unwrap().into_rgba8();
+        let r = rects[*path];
+        for (x, y, px) in original.enumerate_pixels() {
+            assert_eq!(image.get_pixel(r.x as u32 + x, r.y as u32 + y), px);
+        }
+    }
+}
+
+// Swapping every sprite over to the packed texture shouldn't change a single pixel
+#[test]
+fn sprites_draw_the_same_from_an_atlas() {
+    let mut assets = Assets::new();
+    let mut sprites = level_1(&mut assets).unwrap();
+    let mut builder = AtlasBuilder::new();
+    for path in LLAMA_SPRITES {
+        builder.add_file(path, Path::new(path)).unwrap();
+    }
+    let atlas = builder.build();
+
+    let mut separate = Offscreen::new(240, 240);
+    let mut screen = separate.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    for s in sprites.iter_mut().rev() {
+        screen.draw_sprite(s);
+    }
+
+    for s in sprites.iter_mut() {
+        let name = LLAMA_SPRITES
+            .iter()
+            .find(|path| Rc::ptr_eq(&assets.get(path).unwrap(), &s.image))
+            .unwrap();
+        for state in s.animation.states.iter_mut() {
+            state.frames = atlas.frames(name, &state.frames).unwrap();
+        }
+        s.image = Rc::clone(&atlas.texture);
+    }
+    let mut packed = Offscreen::new(240, 240);
+    let mut screen = packed.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    for s in sprites.iter_mut().rev() {
+        screen.draw_sprite(s);
+    }
+    assert_eq!(separate.buffer(), packed.buffer());
+}
+
+#[test]
+fn tiles_draw_the_same_from_an_atlas() {
+    let original = reset_tiles(&mut Assets::new()).unwrap();
+    let mut builder = AtlasBuilder::new();
+    builder
+        .add_file("tiles", Path::new("slug/slug_tiles.png"))
+        .unwrap();
+    builder
+        .add_file("laser", Path::new("llama/sprites/laser.png"))
+        .unwrap();
+    let atlas = builder.build();
+    let rects: Vec<Rect> = atlas.grid("tiles", TILE_SZ, TILE_SZ).unwrap()[..4].to_vec();
+    let tileset =
+        Rc::new(Tileset::with_rects(vec![Tile { solid: true }; 4], &atlas.texture, rects).unwrap());
+    let map = original.map.iter().map(|id| id.0).collect();
+    let packed = Tilemap::new(original.position, original.size(), &tileset, map);
+
+    let mut expected = Offscreen::new(480, 480);
+    original.draw(&mut expected.screen(Vec2i(1, 1)));
+    let mut actual = Offscreen::new(480, 480);
+    packed.draw(&mut actual.screen(Vec2i(1, 1)));
+    assert_eq!(expected.buffer(), actual.buffer());
+}
+
+#[test]
+fn manifests_round_trip() {
+    let mut builder = AtlasBuilder::new();
+    builder.add("wide", RgbaImage::new(20, 4));
+    builder.add("tall", RgbaImage::new(3, 12));
+    let (_, rects) = builder.pack_image();
+    let manifest = Atlas::manifest(&rects);
+    let atlas = Atlas::from_manifest(&builder.build().texture, &manifest).unwrap();
+    assert_eq!(atlas.rect("wide"), Some(rects["wide"]));
+    assert_eq!(atlas.rect("tall"), Some(rects["tall"]));
+    assert_eq!(atlas.rect("missing"), None);
+
+    match Atlas::from_manifest(&atlas.texture, "wide 0 0 20 4\n\ntall 0 x 3 12\n") {
+        Err(Error::Manifest { line, .. }) => assert_eq!(line, 3),
+        _ => panic!("bad manifest accepted"),
+    }
+}
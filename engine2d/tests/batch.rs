@@ -0,0 +1,128 @@
+use engine2d::assets::Assets;
+use engine2d::batch::Batch;
+use engine2d::llama_maker::level_1;
+use engine2d::offscreen::Offscreen;
+use engine2d::render_queue::{Layer, RenderQueue};
+use engine2d::slug_maker::reset_tiles;
+use engine2d::sprite::DrawSpriteExt;
+use engine2d::types::*;
+
+const CAMERAS: [Vec2i; 3] = [Vec2i(0, 0), Vec2i(37, -11), Vec2i(-5, 90)];
+
+#[test]
+fn bands_cover_every_row_once() {
+    let mut offscreen = Offscreen::new(8, 10);
+    let mut screen = offscreen.screen(Vec2i(3, 4));
+    let bands = screen.bands(3);
+    let heights: Vec<usize> = bands.iter().map(|b| b.bounds().h as usize).collect();
+    assert_eq!(heights, vec![3, 3, 3, 1]);
+    let tops: Vec<i32> = bands.iter().map(|b| b.position.1).collect();
+    assert_eq!(tops, vec![4, 7, 10, 13]);
+    assert!(bands.iter().all(|b| b.position.0 == 3));
+}
+
+#[test]
+fn banded_tilemap_matches_drawing_it_all_at_once() {
+    let map = reset_tiles(&mut Assets::new()).unwrap();
+    for &camera in CAMERAS.iter() {
+        let mut expected = Offscreen::new(160, 120);
+        map.draw(&mut expected.screen(camera));
+        let mut batch = Batch::new();
+        batch.extend(map.blits(expected.screen(camera).bounds(), DrawParams::default()));
+        for &rows in [1, 7, 100].iter() {
+            let mut banded = Offscreen::new(160, 120);
+            batch.draw_banded(&mut banded.screen(camera), rows);
+            assert_eq!(
+                banded.buffer(),
+                expected.buffer(),
+                "camera {:?}, {} rows",
+                camera,
+                rows
+            );
+        }
+        let mut parallel = Offscreen::new(160, 120);
+        map.draw_parallel(&mut parallel.screen(camera), DrawParams::default());
+        assert_eq!(parallel.buffer(), expected.buffer());
+    }
+}
+
+#[test]
+fn banded_sprites_match_drawing_them_one_by_one() {
+    let mut sprites = level_1(&mut Assets::new()).unwrap();
+    // Make sure the odd ones are in there too
+    sprites[0].rotation = 0.7;
+    sprites[1].flip_x = true;
+    sprites[1].scale = (1.5, 0.75);
+    sprites[2].opacity = 0.5;
+    sprites[2].flip_y = true;
+    sprites[3].hud = true;
+    for &camera in CAMERAS.iter() {
+        let mut expected = Offscreen::new(200, 150);
+        {
+            let mut screen = expected.screen(camera);
+            screen.clear(Rgba(20, 30, 40, 255));
+            for s in sprites.iter() {
+                screen.draw_sprite(s);
+            }
+        }
+        let mut batch = Batch::new();
+        batch.extend(sprites.iter().map(|s| s.blit()));
+        assert_eq!(batch.len(), sprites.len());
+        for &rows in [1, 7, 100].iter() {
+            let mut banded = Offscreen::new(200, 150);
+            let mut screen = banded.screen(camera);
+            screen.clear(Rgba(20, 30, 40, 255));
+            batch.draw_banded(&mut screen, rows);
+            assert_eq!(
+                banded.buffer(),
+                expected.buffer(),
+                "camera {:?}, {} rows",
+                camera,
+                rows
+            );
+        }
+    }
+}
+
+#[test]
+fn queue_batches_sprites_without_changing_the_order() {
+    let mut sprites = level_1(&mut Assets::new()).unwrap();
+    sprites[1].hud = true;
+    let (back, front) = sprites.split_at(sprites.len() / 2);
+    let cover = Rect {
+        x: 0,
+        y: 40,
+        w: 200,
+        h: 30,
+    };
+    let camera = Vec2i(10, 5);
+    let mut expected = Offscreen::new(200, 150);
+    {
+        let mut screen = expected.screen(camera);
+        for s in back.iter().filter(|s| !s.hud) {
+            screen.draw_sprite(s);
+        }
+        screen.fill_rect(cover, Rgba(0, 255, 0, 128));
+        for s in front.iter() {
+            screen.draw_sprite(s);
+        }
+        for s in back.iter().filter(|s| s.hud) {
+            screen.draw_sprite(s);
+        }
+    }
+    let mut offscreen = Offscreen::new(200, 150);
+    let mut queue = RenderQueue::new();
+    for s in back.iter() {
+        let layer = if s.hud { Layer::Hud } else { Layer::World };
+        queue.sprite(layer, 0, s);
+    }
+    // Something that isn't a sprite in between has to stay in between
+    queue.custom(Layer::World, 1, move |screen| {
+        screen.fill_rect(cover, Rgba(0, 255, 0, 128))
+    });
+    for s in front.iter() {
+        queue.sprite(Layer::World, 2, s);
+    }
+    queue.draw(&mut offscreen.screen(camera));
+    assert_eq!(offscreen.buffer(), expected.buffer());
+}
@@ -0,0 +1,119 @@
+use engine2d::assets::Assets;
+use engine2d::camera::Camera;
+use engine2d::slug_maker::{make_player, reset_tiles};
+use engine2d::types::{Rect, Vec2i};
+
+fn camera() -> Camera {
+    let mut camera = Camera::new((100, 80));
+    // The target can roam the middle 20x20 of the view
+    camera.dead_zone = Rect {
+        x: 40,
+        y: 30,
+        w: 20,
+        h: 20,
+    };
+    camera
+}
+
+#[test]
+fn dead_zone_only_follows_when_left() {
+    let mut camera = camera();
+    camera.follow_point(Vec2i(50, 40));
+    assert_eq!(camera.position(), Vec2i(0, 0));
+    camera.follow_point(Vec2i(60, 50));
+    assert_eq!(camera.position(), Vec2i(0, 0));
+    // Push past the right and bottom edges: the camera keeps the target right on them
+    camera.follow_point(Vec2i(75, 58));
+    assert_eq!(camera.position(), Vec2i(15, 8));
+    // Back inside the zone, nothing moves
+    camera.follow_point(Vec2i(60, 45));
+    assert_eq!(camera.position(), Vec2i(15, 8));
+    // And out the left/top
+    camera.follow_point(Vec2i(40, 30));
+    assert_eq!(camera.position(), Vec2i(0, 0));
+}
+
+#[test]
+fn smoothing_eases_in() {
+    let mut camera = camera();
+    camera.smoothing = 0.5;
+    let mut last = 0;
+    for _ in 0..20 {
+        camera.follow_point(Vec2i(160, 40));
+        let x = camera.position().0;
+        assert!(x >= last);
+        last = x;
+    }
+    assert_eq!(camera.position(), Vec2i(100, 0));
+    camera.follow_point(Vec2i(160, 40));
+    assert_eq!(camera.position(), Vec2i(100, 0));
+}
+
+#[test]
+fn bounds_keep_the_view_on_the_map() {
+    let mut camera = camera();
+    camera.bounds = Some(Rect {
+        x: 0,
+        y: 0,
+        w: 300,
+        h: 200,
+    });
+    camera.follow_point(Vec2i(-50, -50));
+    assert_eq!(camera.position(), Vec2i(0, 0));
+    camera.follow_point(Vec2i(1000, 1000));
+    assert_eq!(camera.position(), Vec2i(200, 120));
+    camera.reset(Vec2i(-10, 500));
+    assert_eq!(camera.position(), Vec2i(0, 120));
+    // A map smaller than the view sits in the middle of it
+    camera.bounds = Some(Rect {
+        x: 10,
+        y: 0,
+        w: 60,
+        h: 80,
+    });
+    camera.follow_point(Vec2i(1000, 1000));
+    assert_eq!(camera.position(), Vec2i(-10, 0));
+}
+
+#[test]
+fn follows_the_middle_of_a_sprite() {
+    let mut assets = Assets::new();
+    let mut player = make_player(&mut assets).unwrap();
+    let mut camera = Camera::new((100, 100));
+    player.position = Vec2i(200, 300);
+    camera.follow(&player);
+    // The player's frames are 48x48, so its middle is 24 in and ends up dead center
+    assert_eq!(camera.position(), Vec2i(200 + 24 - 50, 300 + 24 - 50));
+    // Clamped to the slug map, which is exactly as big as the screen
+    let map = reset_tiles(&mut assets).unwrap();
+    let mut camera = Camera::new((480, 480));
+    camera.bounds = Some(map.bounds());
+    camera.follow(&player);
+    assert_eq!(camera.position(), Vec2i(0, 0));
+}
+
+#[test]
+fn trauma_shakes_then_settles() {
+    let mut camera = camera();
+    camera.reset(Vec2i(20, 10));
+    camera.update();
+    assert_eq!(camera.offset(), camera.position());
+
+    camera.add_trauma(0.7);
+    camera.add_trauma(0.7);
+    assert_eq!(camera.trauma(), 1.0);
+    let mut moved = false;
+    for _ in 0..60 {
+        camera.update();
+        let Vec2i(dx, dy) = camera.offset();
+        let (dx, dy) = (dx - 20, dy - 10);
+        assert!(dx.abs() as f32 <= camera.max_shake && dy.abs() as f32 <= camera.max_shake);
+        moved |= dx != 0 || dy != 0;
+        // Shaking never moves the camera itself
+        assert_eq!(camera.position(), Vec2i(20, 10));
+    }
+    assert!(moved);
+    camera.update();
+    assert_eq!(camera.trauma(), 0.0);
+    assert_eq!(camera.offset(), Vec2i(20, 10));
+}
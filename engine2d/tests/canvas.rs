@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use engine2d::assets::Assets;
+use engine2d::dirty::DirtyRects;
+use engine2d::offscreen::Offscreen;
+use engine2d::screen::Screen;
+use engine2d::slug_maker::reset_tiles;
+use engine2d::texture::Texture;
+use engine2d::types::{Rect, Rgba, Transform, Vec2i};
+
+fn whole(texture: &Texture) -> Rect {
+    let (w, h) = texture.size();
+    Rect {
+        x: 0,
+        y: 0,
+        w: w as u16,
+        h: h as u16,
+    }
+}
+
+#[test]
+fn blank_textures_are_transparent() {
+    let texture = Texture::blank(3, 2);
+    assert_eq!(texture.size(), (3, 2));
+    assert!(texture.buffer().iter().all(|&b| b == 0));
+}
+
+#[test]
+fn drawing_into_a_texture_then_blitting_it_matches_drawing_directly() {
+    let sprite = gradient_texture(8, 8);
+    let draw = |screen: &mut Screen| {
+        screen.clear(Rgba(10, 20, 30, 255));
+        screen.bitblt(&sprite, whole(&sprite), Vec2i(13, 5));
+        screen.fill_circle(Vec2i(16, 10), 4, Rgba(255, 0, 0, 128));
+    };
+    // The canvas looks at the world from (10, 3)
+    let mut texture = Texture::blank(16, 12);
+    draw(&mut texture.canvas(Vec2i(10, 3)));
+    let mut direct = Offscreen::new(16, 12);
+    draw(&mut direct.screen(Vec2i(10, 3)));
+    let mut through = Offscreen::new(16, 12);
+    through
+        .screen(Vec2i(0, 0))
+        .bitblt(&texture, whole(&texture), Vec2i(0, 0));
+    assert_eq!(through.buffer(), direct.buffer());
+}
+
+#[test]
+fn baked_tilemap_looks_like_the_tilemap() {
+    let map = reset_tiles(&mut Assets::new()).unwrap();
+    let baked = map.bake();
+    let bounds = map.bounds();
+    assert_eq!(baked.size(), (bounds.w as usize, bounds.h as usize));
+    for &camera in [Vec2i(0, 0), Vec2i(50, 37)].iter() {
+        let mut expected = Offscreen::new(120, 90);
+        map.draw(&mut expected.screen(camera));
+        let mut actual = Offscreen::new(120, 90);
+        actual
+            .screen(camera)
+            .bitblt(&baked, whole(&baked), map.position);
+        assert_eq!(actual.buffer(), expected.buffer());
+    }
+}
+
+#[test]
+fn thumbnail_scales_a_texture_down() {
+    let big = solid_texture(32, 32, [0, 255, 0, 255]);
+    let mut thumbnail = Texture::blank(8, 8);
+    let quarter = Transform {
+        rotation: 0.0,
+        scale: (0.25, 0.25),
+        pivot: (0.0, 0.0),
+    };
+    thumbnail.canvas(Vec2i(0, 0)).bitblt_transformed(
+        &big,
+        whole(&big),
+        Vec2i(0, 0),
+        quarter,
+        Default::default(),
+    );
+    assert!(thumbnail
+        .buffer()
+        .chunks_exact(4)
+        .all(|px| px == [0, 255, 0, 255]));
+}
+
+#[test]
+fn drawing_into_a_texture_counts_as_a_change() {
+    let mut texture = Texture::blank(4, 4);
+    let before = texture.id();
+    texture.canvas(Vec2i(0, 0)).clear(Rgba(255, 255, 255, 255));
+    assert_ne!(texture.id(), before);
+    // So a screen showing it notices it's different
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(8, 8);
+    let mut draw = |texture: &Texture, offscreen: &mut Offscreen| {
+        dirty.draw(&mut offscreen.screen(Vec2i(0, 0)), |screen| {
+            screen.clear(Rgba(0, 0, 0, 255));
+            screen.bitblt(texture, whole(texture), Vec2i(2, 2));
+        })
+    };
+    draw(&texture, &mut offscreen);
+    assert!(!draw(&texture, &mut offscreen));
+    texture.buffer_mut()[0..4].copy_from_slice(&[255, 0, 0, 255]);
+    assert!(draw(&texture, &mut offscreen));
+    assert_eq!(pixel(&offscreen, 2, 2), [255, 0, 0, 255]);
+}
@@ -0,0 +1,110 @@
+// Golden-image harness shared by the rendering tests.
+// Scenes get drawn into an `Offscreen` and compared against PNGs checked in under tests/golden.
+// Run with UPDATE_GOLDEN=1 to (re)write the reference images after an intentional change.
+#![allow(dead_code)]
+use engine2d::offscreen::Offscreen;
+use engine2d::texture::Texture;
+use image::{Rgba, RgbaImage};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Per-channel difference we put up with before calling a pixel wrong
+pub const TOLERANCE: u8 = 1;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+fn failure_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden-failures")
+}
+
+/// Compare what's been drawn into `offscreen` with `tests/golden/<name>.png`.
+/// On a mismatch the actual image and a diff image (differing pixels in red) are written to
+/// target/golden-failures and the test panics.
+pub fn check_golden(name: &str, offscreen: &Offscreen, tolerance: u8) {
+    let actual = offscreen.to_image();
+    let path = golden_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        offscreen
+            .save_png(&path)
+            .expect("Couldn't write golden image");
+        return;
+    }
+    let expected = match image::open(&path) {
+        Ok(img) => img.into_rgba8(),
+        Err(e) => panic!(
+            "Couldn't load golden image {}: {} (run with UPDATE_GOLDEN=1 to create it)",
+            path.display(),
+            e
+        ),
+    };
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "Golden image {} is the wrong size",
+        name
+    );
+    let (w, h) = actual.dimensions();
+    let mut diff = RgbaImage::new(w, h);
+    let mut bad = 0;
+    let mut first_bad = None;
+    for (x, y, px) in actual.enumerate_pixels() {
+        let want = expected.get_pixel(x, y);
+        let off =
+            px.0.iter()
+                .zip(want.0.iter())
+                .any(|(a, b)| (*a as i16 - *b as i16).abs() > tolerance as i16);
+        if off {
+            bad += 1;
+            first_bad.get_or_insert((x, y, want.0, px.0));
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            // Dim copy of the expected image so the red stands out
+            diff.put_pixel(x, y, Rgba([want[0] / 3, want[1] / 3, want[2] / 3, 255]));
+        }
+    }
+    if let Some((x, y, want, got)) = first_bad {
+        let dir = failure_dir();
+        fs::create_dir_all(&dir).expect("Couldn't create failure directory");
+        let actual_path = dir.join(format!("{}.actual.png", name));
+        let diff_path = dir.join(format!("{}.diff.png", name));
+        actual
+            .save(&actual_path)
+            .expect("Couldn't write actual image");
+        diff.save(&diff_path).expect("Couldn't write diff image");
+        panic!(
+            "{} pixels differ from golden image {} (first at ({}, {}): expected {:?}, got {:?}); see {}",
+            bad,
+            name,
+            x,
+            y,
+            want,
+            got,
+            diff_path.display()
+        );
+    }
+}
+
+/// A texture of `w`x`h` pixels all of one (straight alpha) color
+pub fn solid_texture(w: u32, h: u32, col: [u8; 4]) -> Texture {
+    Texture::new(RgbaImage::from_pixel(w, h, Rgba(col)))
+}
+
+/// A texture where every pixel is a different opaque color, so clipping mistakes are visible
+pub fn gradient_texture(w: u32, h: u32) -> Texture {
+    Texture::new(RgbaImage::from_fn(w, h, |x, y| {
+        Rgba([(x * 16) as u8, (y * 16) as u8, 200, 255])
+    }))
+}
+
+/// Read one pixel back out of an offscreen buffer
+pub fn pixel(offscreen: &Offscreen, x: usize, y: usize) -> [u8; 4] {
+    let (w, _h) = offscreen.size();
+    let i = (y * w + x) * 4;
+    let buf = offscreen.buffer();
+    [buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]
+}
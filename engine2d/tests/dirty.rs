@@ -0,0 +1,247 @@
+mod common;
+
+use common::*;
+use engine2d::dirty::DirtyRects;
+use engine2d::offscreen::Offscreen;
+use engine2d::screen::Screen;
+use engine2d::texture::Texture;
+use engine2d::types::{Rect, Rgba, Vec2i};
+
+const BLOCK: Rect = Rect {
+    x: 0,
+    y: 0,
+    w: 8,
+    h: 8,
+};
+
+// A little scene: a backdrop, some blocks (drawn in order) and a translucent square over it all
+struct Scene {
+    background: Rgba,
+    backdrop: Texture,
+    block: Texture,
+    blocks: Vec<Vec2i>,
+}
+
+impl Scene {
+    fn new() -> Self {
+        Self {
+            background: Rgba(0, 0, 40, 255),
+            backdrop: gradient_texture(16, 16),
+            block: solid_texture(8, 8, [255, 0, 0, 160]),
+            blocks: vec![Vec2i(4, 4), Vec2i(40, 30), Vec2i(44, 34)],
+        }
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        screen.clear(self.background);
+        screen.bitblt(
+            &self.backdrop,
+            Rect {
+                w: 16,
+                h: 16,
+                ..BLOCK
+            },
+            Vec2i(20, 20),
+        );
+        for &at in self.blocks.iter() {
+            screen.bitblt(&self.block, BLOCK, at);
+        }
+        screen.fill_rect(
+            Rect {
+                x: 50,
+                y: 2,
+                w: 10,
+                h: 10,
+            },
+            Rgba(0, 255, 0, 100),
+        );
+    }
+}
+
+// Draw one frame through `dirty`, returning whether anything was redrawn
+fn frame(dirty: &mut DirtyRects, offscreen: &mut Offscreen, camera: Vec2i, scene: &Scene) -> bool {
+    dirty.draw(&mut offscreen.screen(camera), |screen| scene.draw(screen))
+}
+
+fn from_scratch(scene: &Scene, camera: Vec2i) -> Offscreen {
+    let mut offscreen = Offscreen::new(64, 48);
+    scene.draw(&mut offscreen.screen(camera));
+    offscreen
+}
+
+#[test]
+fn first_frame_draws_everything() {
+    let scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(
+        dirty.redrawn(),
+        &[Rect {
+            x: 0,
+            y: 0,
+            w: 64,
+            h: 48
+        }]
+    );
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+}
+
+#[test]
+fn same_frame_again_draws_nothing() {
+    let scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    // Scribble on it; if nothing gets redrawn the scribble stays
+    offscreen
+        .screen(Vec2i(0, 0))
+        .put_pixel(Vec2i(1, 1), Rgba(255, 255, 255, 255));
+    assert!(!frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert!(dirty.redrawn().is_empty());
+    assert_eq!(pixel(&offscreen, 1, 1), [255, 255, 255, 255]);
+}
+
+#[test]
+fn moving_something_redraws_just_where_it_was_and_is() {
+    let mut scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    scene.blocks[0] = Vec2i(6, 5);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(
+        dirty.redrawn(),
+        &[Rect {
+            x: 4,
+            y: 4,
+            w: 10,
+            h: 9
+        }]
+    );
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+}
+
+#[test]
+fn changes_under_other_things_come_out_right() {
+    let mut scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    // The middle block is under the last one and over the backdrop
+    scene.blocks[1] = Vec2i(38, 31);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+    // Swapping the overlapping ones around changes which is on top, without changing what's drawn
+    scene.blocks.swap(1, 2);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+    // So does adding and taking away
+    scene.blocks.push(Vec2i(58, 44));
+    scene.blocks.remove(0);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+}
+
+#[test]
+fn changing_the_clear_color_redraws_everything() {
+    let mut scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    scene.background = Rgba(40, 0, 0, 255);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(dirty.redrawn()[0].w, 64);
+    assert_eq!(pixel(&offscreen, 1, 1), [40, 0, 0, 255]);
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(0, 0)).buffer()
+    );
+}
+
+#[test]
+fn moving_the_camera_redraws_everything() {
+    let scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(3, -2), &scene));
+    assert_eq!(dirty.redrawn().len(), 1);
+    assert_eq!(dirty.redrawn()[0].w, 64);
+    assert_eq!(
+        offscreen.buffer(),
+        from_scratch(&scene, Vec2i(3, -2)).buffer()
+    );
+}
+
+#[test]
+fn marked_and_invalidated_areas_get_redrawn() {
+    let scene = Scene::new();
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(64, 48);
+    frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene);
+    let area = Rect {
+        x: 10,
+        y: 10,
+        w: 5,
+        h: 5,
+    };
+    dirty.mark(area);
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(dirty.redrawn(), &[area]);
+    dirty.invalidate();
+    assert!(frame(&mut dirty, &mut offscreen, Vec2i(0, 0), &scene));
+    assert_eq!(dirty.redrawn()[0].w, 64);
+}
+
+#[test]
+fn clip_keeps_drawing_inside_it() {
+    let mut offscreen = Offscreen::new(16, 16);
+    let mut screen = offscreen.screen(Vec2i(-2, -2));
+    screen.set_clip(Rect {
+        x: 4,
+        y: 4,
+        w: 4,
+        h: 4,
+    });
+    screen.clear(Rgba(0, 0, 255, 255));
+    screen.bitblt(
+        &solid_texture(16, 16, [255, 0, 0, 255]),
+        Rect {
+            x: 0,
+            y: 0,
+            w: 16,
+            h: 16,
+        },
+        Vec2i(0, 0),
+    );
+    screen.fill_circle(Vec2i(4, 4), 10, Rgba(0, 255, 0, 255));
+    screen.line(Vec2i(-2, -2), Vec2i(13, 13), Rgba(255, 255, 255, 255));
+    for y in 0..16 {
+        for x in 0..16 {
+            let inside = (4..8).contains(&x) && (4..8).contains(&y);
+            assert_eq!(
+                pixel(&offscreen, x, y)[3] != 0,
+                inside,
+                "pixel {}, {}",
+                x,
+                y
+            );
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use engine2d::animation::Animation;
+use engine2d::assets::Assets;
+use engine2d::error::Error;
+use engine2d::llama_maker::{level_1, level_2, level_3};
+use engine2d::offscreen::Offscreen;
+use engine2d::slug_maker::{make_core, reset_tiles};
+use engine2d::sprite::Sprite;
+use engine2d::texture::Texture;
+use engine2d::tile::{Tile, Tileset};
+use engine2d::types::{AnimationState, Rect, Vec2i};
+use image::RgbaImage;
+use std::rc::Rc;
+
+fn texture(w: u32, h: u32) -> Rc<Texture> {
+    Rc::new(Texture::new(RgbaImage::new(w, h)))
+}
+
+fn state(frames: Vec<Rect>) -> AnimationState {
+    AnimationState {
+        frames,
+        current_index: 0,
+        start_time: 0,
+        repeat: true,
+    }
+}
+
+fn rect(x: i32, y: i32, w: u16, h: u16) -> Rect {
+    Rect { x, y, w, h }
+}
+
+fn sprite(tex: &Rc<Texture>, states: Vec<AnimationState>) -> Result<Sprite, Error> {
+    Sprite::new(
+        tex,
+        Animation::new(states),
+        Vec2i(0, 0),
+        vec![],
+        false,
+        false,
+        false,
+    )
+}
+
+// Every hand-written frame in the games has to fit its texture
+#[test]
+fn game_content_is_valid() {
+    let mut assets = Assets::new();
+    level_1(&mut assets).unwrap();
+    level_2(&mut assets).unwrap();
+    level_3(&mut assets).unwrap();
+    make_core(&mut assets).unwrap();
+    reset_tiles(&mut assets).unwrap();
+}
+
+#[test]
+fn frames_touching_the_edges_are_fine() {
+    let tex = texture(64, 32);
+    let frames = vec![rect(0, 0, 32, 32), rect(32, 0, 32, 32)];
+    assert!(sprite(&tex, vec![state(frames)]).is_ok());
+}
+
+#[test]
+fn off_by_one_frames_are_reported() {
+    let tex = texture(64, 32);
+    let good = state(vec![rect(0, 0, 32, 32)]);
+    let bad = state(vec![rect(0, 0, 32, 32), rect(33, 0, 32, 32)]);
+    match sprite(&tex, vec![good, bad]) {
+        Err(Error::BadFrame {
+            state,
+            frame,
+            rect: r,
+            size,
+        }) => {
+            assert_eq!((state, frame), (1, 1));
+            assert_eq!(r, rect(33, 0, 32, 32));
+            assert_eq!(size, (64, 32));
+        }
+        _ => panic!("bad frame accepted"),
+    }
+    // The message says where to look
+    let err = sprite(&tex, vec![state(vec![rect(0, 1, 32, 32)])])
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("state 0 frame 0"), "{}", err);
+}
+
+#[test]
+fn negative_and_empty_frames_are_reported() {
+    let tex = texture(16, 16);
+    assert!(matches!(
+        sprite(&tex, vec![state(vec![rect(-1, 0, 4, 4)])]),
+        Err(Error::BadFrame { .. })
+    ));
+    assert!(matches!(
+        sprite(&tex, vec![state(vec![rect(0, 0, 4, 4)]), state(vec![])]),
+        Err(Error::NoFrames { state: 1 })
+    ));
+}
+
+#[test]
+fn tilesets_need_a_cell_per_tile() {
+    // Room for two 48x48 tiles, but three are asked for
+    let tex = texture(96, 48);
+    let tiles = vec![Tile { solid: false }; 3];
+    assert!(Tileset::new(tiles[..2].to_vec(), &tex).is_ok());
+    assert!(matches!(
+        Tileset::new(tiles.clone(), &tex),
+        Err(Error::BadTile { tile: 2, .. })
+    ));
+    assert!(matches!(
+        Tileset::with_rects(
+            tiles,
+            &tex,
+            vec![rect(0, 0, 48, 48), rect(48, 0, 48, 48), rect(49, 0, 48, 48)]
+        ),
+        Err(Error::BadTile { tile: 2, .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "runs outside")]
+fn blitting_past_the_right_edge_panics() {
+    let tex = texture(16, 16);
+    let mut offscreen = Offscreen::new(32, 32);
+    offscreen
+        .screen(Vec2i(0, 0))
+        .bitblt(&tex, rect(8, 0, 9, 4), Vec2i(0, 0));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::*;
+use engine2d::offscreen::Offscreen;
+use engine2d::parallax::{Backdrop, Parallax};
+use engine2d::types::Vec2i;
+use std::rc::Rc;
+
+// A 4x4 gradient with its top-left at `position`
+fn layer(position: Vec2i, factor: (f32, f32), wrap: (bool, bool)) -> Parallax {
+    let mut layer = Parallax::new(
+        Backdrop::Texture {
+            texture: Rc::new(gradient_texture(4, 4)),
+            position,
+        },
+        factor,
+    );
+    layer.wrap = wrap;
+    layer
+}
+
+// Draw `layer` on a 10x10 screen and check each pixel shows the gradient pixel `src_at` says
+fn check_layer(layer: &Parallax, camera: Vec2i, src_at: impl Fn(i32, i32) -> Option<(i32, i32)>) {
+    let mut offscreen = Offscreen::new(10, 10);
+    let mut screen = offscreen.screen(camera);
+    layer.draw(&mut screen);
+    assert_eq!(screen.position, camera, "drawing moved the camera");
+    for y in 0..10 {
+        for x in 0..10 {
+            let want = match src_at(x, y) {
+                Some((sx, sy)) => [(sx * 16) as u8, (sy * 16) as u8, 200, 255],
+                None => [0, 0, 0, 0],
+            };
+            let got = pixel(&offscreen, x as usize, y as usize);
+            assert_eq!(got, want, "at ({}, {}) with camera {:?}", x, y, camera);
+        }
+    }
+}
+
+fn inside(x: i32, y: i32) -> Option<(i32, i32)> {
+    if (0..4).contains(&x) && (0..4).contains(&y) {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn scrolls_at_a_fraction_of_the_camera() {
+    let layer = layer(Vec2i(3, 2), (0.5, 0.25), (false, false));
+    // Seen from (8, 8) the layer's only moved by (4, 2)
+    check_layer(&layer, Vec2i(8, 8), |x, y| inside(x + 4 - 3, y + 2 - 2));
+    // A factor of 0 pins it to the screen
+    let pinned = self::layer(Vec2i(3, 2), (0.0, 0.0), (false, false));
+    check_layer(&pinned, Vec2i(-50, 70), |x, y| inside(x - 3, y - 2));
+}
+
+#[test]
+fn wraps_horizontally() {
+    let layer = layer(Vec2i(1, 3), (1.0, 1.0), (true, false));
+    for &camera in &[Vec2i(0, 0), Vec2i(7, 1), Vec2i(-13, 0), Vec2i(402, -2)] {
+        check_layer(&layer, camera, |x, y| {
+            let (wx, wy) = (x + camera.0, y + camera.1);
+            inside((wx - 1).rem_euclid(4), wy - 3)
+        });
+    }
+}
+
+#[test]
+fn wraps_both_ways() {
+    let layer = layer(Vec2i(-2, 5), (0.5, 0.5), (true, true));
+    // Half of (9, 31) rounds to (5, 16)
+    for &(camera, view) in &[
+        (Vec2i(0, 0), Vec2i(0, 0)),
+        (Vec2i(9, 31), Vec2i(5, 16)),
+        (Vec2i(-22, -6), Vec2i(-11, -3)),
+    ] {
+        check_layer(&layer, camera, |x, y| {
+            let (wx, wy) = (x + view.0, y + view.1);
+            Some(((wx + 2).rem_euclid(4), (wy - 5).rem_euclid(4)))
+        });
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::*;
+use engine2d::offscreen::Offscreen;
+use engine2d::particles::{Emitter, Shape};
+use engine2d::types::{Rect, Rgba, Vec2i};
+use std::rc::Rc;
+
+// Particles that all head right at 2 pixels per frame and last 5 frames
+fn steady(shape: Shape) -> Emitter {
+    let mut emitter = Emitter::new(Vec2i(10, 20), shape);
+    emitter.angle = 0.0..0.0;
+    emitter.speed = 2.0..2.0;
+    emitter.lifetime = 5..5;
+    emitter.seed(7);
+    emitter
+}
+
+#[test]
+fn bursts_move_and_expire() {
+    let mut emitter = steady(Shape::Square);
+    emitter.gravity = 0.5;
+    emitter.burst(3);
+    assert_eq!(emitter.len(), 3);
+    emitter.update();
+    emitter.update();
+    for p in emitter.particles() {
+        assert_eq!(p.age, 2);
+        assert_eq!(p.position, (14.0, 21.5));
+        assert_eq!(p.velocity, (2.0, 1.0));
+    }
+    // Moving the emitter doesn't drag its particles along
+    emitter.position = Vec2i(-100, -100);
+    emitter.update();
+    assert_eq!(emitter.particles()[0].position.0, 16.0);
+    emitter.update();
+    emitter.update();
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn continuous_rate_adds_up() {
+    let mut emitter = steady(Shape::Square);
+    emitter.rate = 0.5;
+    emitter.update();
+    assert_eq!(emitter.len(), 0);
+    emitter.update();
+    assert_eq!(emitter.len(), 1);
+    for _ in 0..4 {
+        emitter.update();
+    }
+    // Two more came along, and the first has had its 5 frames
+    assert_eq!(emitter.len(), 2);
+    emitter.rate = 0.0;
+    emitter.clear();
+    emitter.update();
+    assert!(emitter.is_empty());
+}
+
+#[test]
+fn random_choices_stay_in_range() {
+    let mut emitter = Emitter::new(Vec2i(0, 0), Shape::Circle);
+    emitter.speed = 1.0..3.0;
+    emitter.radius = 4.0;
+    emitter.lifetime = 10..20;
+    emitter.burst(200);
+    for p in emitter.particles() {
+        let (x, y) = p.position;
+        assert!(x * x + y * y <= 16.0 + 1e-3, "{:?} started too far out", p);
+        let (vx, vy) = p.velocity;
+        let speed = (vx * vx + vy * vy).sqrt();
+        assert!((1.0 - 1e-3..3.0 + 1e-3).contains(&speed), "{:?}", p);
+        assert!(p.lifetime >= 10 && p.lifetime < 20);
+    }
+}
+
+#[test]
+fn color_and_size_change_over_life() {
+    let mut emitter = steady(Shape::Square);
+    emitter.speed = 0.0..0.0;
+    emitter.lifetime = 4..4;
+    emitter.color = (Rgba(255, 0, 0, 255), Rgba(0, 0, 255, 255));
+    emitter.size = (3.0, 1.0);
+    emitter.position = Vec2i(4, 4);
+    emitter.burst(1);
+    let mut offscreen = Offscreen::new(8, 8);
+    emitter.draw(&mut offscreen.screen(Vec2i(0, 0)));
+    let lit: Vec<(usize, usize)> = (0..64)
+        .map(|i| (i % 8, i / 8))
+        .filter(|&(x, y)| pixel(&offscreen, x, y)[3] != 0)
+        .collect();
+    assert_eq!(lit.len(), 9);
+    assert_eq!(pixel(&offscreen, 4, 4), [255, 0, 0, 255]);
+    // Halfway: two pixels across and half way to blue
+    emitter.update();
+    emitter.update();
+    let mut offscreen = Offscreen::new(8, 8);
+    emitter.draw(&mut offscreen.screen(Vec2i(0, 0)));
+    let lit = (0..64)
+        .filter(|i| pixel(&offscreen, i % 8, i / 8)[3] != 0)
+        .count();
+    assert_eq!(lit, 4);
+    assert_eq!(pixel(&offscreen, 4, 4), [128, 0, 128, 255]);
+}
+
+#[test]
+fn frames_are_tinted_and_follow_the_camera() {
+    let tex = Rc::new(gradient_texture(4, 4));
+    let frame = Rect {
+        x: 0,
+        y: 0,
+        w: 4,
+        h: 4,
+    };
+    let mut emitter = steady(Shape::Frame(tex, frame));
+    emitter.speed = 0.0..0.0;
+    emitter.size = (4.0, 4.0);
+    emitter.color = (Rgba(255, 255, 255, 255), Rgba(255, 255, 255, 255));
+    emitter.position = Vec2i(12, 12);
+    emitter.burst(1);
+    let mut offscreen = Offscreen::new(8, 8);
+    emitter.draw(&mut offscreen.screen(Vec2i(8, 8)));
+    // The frame is centered on the particle, so its top-left is at (10, 10) in the world
+    for y in 0..4 {
+        for x in 0..4 {
+            let want = [(x * 16) as u8, (y * 16) as u8, 200, 255];
+            assert_eq!(pixel(&offscreen, x + 2, y + 2), want);
+        }
+    }
+    assert_eq!(pixel(&offscreen, 1, 1), [0, 0, 0, 0]);
+}
@@ -0,0 +1,102 @@
+use engine2d::assets::Assets;
+use engine2d::recolor::Recolor;
+use engine2d::slug_maker::{make_player, make_team_player};
+use engine2d::texture::Texture;
+use engine2d::types::Rgba;
+use image::RgbaImage;
+use std::path::Path;
+use std::rc::Rc;
+
+fn image_of(colors: &[[u8; 4]]) -> RgbaImage {
+    let mut img = RgbaImage::new(colors.len() as u32, 1);
+    for (x, c) in colors.iter().enumerate() {
+        img.put_pixel(x as u32, 0, image::Rgba(*c));
+    }
+    img
+}
+
+#[test]
+fn palette_swaps_keep_premultiplied_alpha() {
+    let green = [107, 242, 12];
+    let red = [244, 22, 22];
+    // The same green at a few opacities, plus a color that isn't in the palette
+    let before = image_of(&[
+        [107, 242, 12, 255],
+        [107, 242, 12, 128],
+        [107, 242, 12, 7],
+        [1, 2, 3, 200],
+        [0, 0, 0, 0],
+    ]);
+    let after = image_of(&[
+        [244, 22, 22, 255],
+        [244, 22, 22, 128],
+        [244, 22, 22, 7],
+        [1, 2, 3, 200],
+        [0, 0, 0, 0],
+    ]);
+    let swap = Recolor::Palette(vec![(
+        Rgba(green[0], green[1], green[2], 255),
+        Rgba(red[0], red[1], red[2], 255),
+    )]);
+    let recolored = Texture::new(before).recolored(&swap);
+    // Exactly what loading the swapped image would have given us
+    assert_eq!(recolored.buffer(), Texture::new(after).buffer());
+}
+
+#[test]
+fn hue_shifts_rotate_colors() {
+    let tex = Texture::new(image_of(&[
+        [255, 0, 0, 255],
+        [0, 255, 0, 255],
+        [128, 128, 128, 255],
+        [255, 0, 0, 102],
+    ]));
+    let third = tex.recolored(&Recolor::HueShift(120.0));
+    let expected = Texture::new(image_of(&[
+        [0, 255, 0, 255],
+        [0, 0, 255, 255],
+        [128, 128, 128, 255],
+        [0, 255, 0, 102],
+    ]));
+    assert_eq!(third.buffer(), expected.buffer());
+    // A full turn (or none) gives back what we started with, give or take rounding
+    for &turn in &[0.0, 360.0, -720.0] {
+        let same = tex.recolored(&Recolor::HueShift(turn));
+        for (a, b) in same.buffer().iter().zip(tex.buffer()) {
+            assert!((*a as i32 - *b as i32).abs() <= 1);
+        }
+    }
+}
+
+// Palette-swapping the player's green body to the enemy's red gets us the enemy,
+// apart from the enemy's eyebrows
+#[test]
+fn recolored_slug_matches_enemy_body() {
+    let red = Rgba(244, 22, 22, 255);
+    let mut assets = Assets::new();
+    let team = make_team_player(&mut assets, red).unwrap();
+    let enemy = assets.texture(Path::new("slug/enemy.png")).unwrap();
+    let differing = team
+        .image
+        .buffer()
+        .chunks_exact(4)
+        .zip(enemy.buffer().chunks_exact(4))
+        .filter(|(a, b)| a != b)
+        .count();
+    assert_eq!(differing, 24);
+}
+
+#[test]
+fn recolors_are_made_once() {
+    let mut assets = Assets::new();
+    let blue = Rgba(30, 60, 230, 255);
+    let first = make_team_player(&mut assets, blue).unwrap();
+    let second = make_team_player(&mut assets, blue).unwrap();
+    assert!(Rc::ptr_eq(&first.image, &second.image));
+    // The original slug plus one recolor
+    assert_eq!(assets.len(), 2);
+    let plain = make_player(&mut assets).unwrap();
+    assert!(!Rc::ptr_eq(&plain.image, &first.image));
+    make_team_player(&mut assets, Rgba(250, 200, 0, 255)).unwrap();
+    assert_eq!(assets.len(), 3);
+}
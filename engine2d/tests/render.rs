@@ -0,0 +1,697 @@
+mod common;
+
+use common::*;
+use engine2d::animation::Animation;
+use engine2d::assets::Assets;
+use engine2d::dirty::DirtyRects;
+use engine2d::llama_maker::level_1;
+use engine2d::offscreen::Offscreen;
+use engine2d::slug_maker::reset_tiles;
+use engine2d::sprite::{DrawSpriteExt, Sprite};
+use engine2d::texture::Texture;
+use engine2d::types::{AnimationState, BlendMode, DrawParams, Rect, Rgba, Transform, Vec2i};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::path::Path;
+use std::rc::Rc;
+
+// The golden scenes load the game assets with relative paths, just like the binaries.
+// Cargo runs integration tests from the crate root, so that works out.
+
+#[test]
+fn slug_tilemap_matches_golden() {
+    let mut offscreen = Offscreen::new(480, 480);
+    let mut screen = offscreen.screen(Vec2i(1, 1));
+    screen.clear(Rgba(80, 80, 80, 255));
+    reset_tiles(&mut Assets::new()).unwrap().draw(&mut screen);
+    check_golden("slug_tilemap", &offscreen, TOLERANCE);
+}
+
+#[test]
+fn llama_level_1_sprites_match_golden() {
+    let mut offscreen = Offscreen::new(240, 240);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    for s in level_1(&mut Assets::new()).unwrap().iter_mut().rev() {
+        screen.draw_sprite(s);
+    }
+    check_golden("llama_level_1", &offscreen, TOLERANCE);
+}
+
+// Blit a 4x4 gradient somewhere on an 8x8 screen and check every pixel:
+// those covered by the blit must come from `src_at`, the rest must stay clear.
+fn check_blit(
+    camera: Vec2i,
+    from: Rect,
+    to: Vec2i,
+    src_at: impl Fn(i32, i32) -> Option<(i32, i32)>,
+) {
+    check_blit_with(camera, from, to, DrawParams::default(), src_at);
+}
+
+fn check_blit_with(
+    camera: Vec2i,
+    from: Rect,
+    to: Vec2i,
+    params: DrawParams,
+    src_at: impl Fn(i32, i32) -> Option<(i32, i32)>,
+) {
+    let tex = gradient_texture(4, 4);
+    let mut offscreen = Offscreen::new(8, 8);
+    offscreen.screen(camera).bitblt_with(&tex, from, to, params);
+    for y in 0..8 {
+        for x in 0..8 {
+            let want = match src_at(x, y) {
+                Some((sx, sy)) => [(sx * 16) as u8, (sy * 16) as u8, 200, 255],
+                None => [0, 0, 0, 0],
+            };
+            assert_eq!(
+                pixel(&offscreen, x as usize, y as usize),
+                want,
+                "wrong pixel at ({}, {}) blitting {:?} to {:?} with {:?}",
+                x,
+                y,
+                from,
+                to,
+                params
+            );
+        }
+    }
+}
+
+const FULL: Rect = Rect {
+    x: 0,
+    y: 0,
+    w: 4,
+    h: 4,
+};
+
+const FULL_1: Rect = Rect {
+    x: 0,
+    y: 0,
+    w: 1,
+    h: 1,
+};
+
+// Where a pixel of the screen lands in a 4x4 source drawn with its top-left at (ox, oy)
+fn inside(x: i32, y: i32, ox: i32, oy: i32, from: Rect) -> Option<(i32, i32)> {
+    let (sx, sy) = (x - ox, y - oy);
+    if sx >= 0 && sx < from.w as i32 && sy >= 0 && sy < from.h as i32 {
+        Some((sx + from.x, sy + from.y))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn bitblt_clips_top_left_edge() {
+    check_blit(Vec2i(0, 0), FULL, Vec2i(-2, -3), |x, y| {
+        inside(x, y, -2, -3, FULL)
+    });
+}
+
+#[test]
+fn bitblt_clips_bottom_right_edge() {
+    check_blit(Vec2i(0, 0), FULL, Vec2i(6, 7), |x, y| {
+        inside(x, y, 6, 7, FULL)
+    });
+}
+
+#[test]
+fn bitblt_just_off_screen_draws_nothing() {
+    for to in &[Vec2i(-4, 0), Vec2i(8, 0), Vec2i(0, -4), Vec2i(0, 8)] {
+        check_blit(Vec2i(0, 0), FULL, *to, |_, _| None);
+    }
+}
+
+#[test]
+fn bitblt_one_pixel_on_screen() {
+    for &(tx, ty) in &[(-3, -3), (7, -3), (-3, 7), (7, 7)] {
+        check_blit(Vec2i(0, 0), FULL, Vec2i(tx, ty), |x, y| {
+            inside(x, y, tx, ty, FULL)
+        });
+    }
+}
+
+#[test]
+fn bitblt_clips_sub_rect() {
+    let from = Rect {
+        x: 1,
+        y: 1,
+        w: 3,
+        h: 2,
+    };
+    check_blit(Vec2i(0, 0), from, Vec2i(6, -1), |x, y| {
+        inside(x, y, 6, -1, from)
+    });
+}
+
+#[test]
+fn bitblt_respects_camera() {
+    check_blit(Vec2i(3, -2), FULL, Vec2i(1, -2), |x, y| {
+        inside(x, y, -2, 0, FULL)
+    });
+}
+
+// Mirror a source pixel found by `inside` within the rect it came from
+fn flipped(p: Option<(i32, i32)>, from: Rect, params: DrawParams) -> Option<(i32, i32)> {
+    p.map(|(x, y)| {
+        let x = if params.flip_x {
+            2 * from.x + from.w as i32 - 1 - x
+        } else {
+            x
+        };
+        let y = if params.flip_y {
+            2 * from.y + from.h as i32 - 1 - y
+        } else {
+            y
+        };
+        (x, y)
+    })
+}
+
+#[test]
+fn bitblt_flips() {
+    let from = Rect {
+        x: 1,
+        y: 0,
+        w: 3,
+        h: 4,
+    };
+    for &(flip_x, flip_y) in &[(true, false), (false, true), (true, true)] {
+        let params = DrawParams {
+            flip_x,
+            flip_y,
+            ..DrawParams::default()
+        };
+        // On screen, clipped on the top-left and clipped on the bottom-right
+        for &(tx, ty) in &[(2, 2), (-1, -2), (6, 5)] {
+            check_blit_with(Vec2i(0, 0), from, Vec2i(tx, ty), params, |x, y| {
+                flipped(inside(x, y, tx, ty, from), from, params)
+            });
+        }
+    }
+}
+
+#[test]
+fn bitblt_transformed_identity_matches_bitblt() {
+    let tex = gradient_texture(4, 4);
+    let identity = Transform {
+        rotation: 0.0,
+        scale: (1.0, 1.0),
+        pivot: (2.0, 2.0),
+    };
+    for &(tx, ty) in &[(2, 2), (-1, -2), (6, 5)] {
+        let mut plain = Offscreen::new(8, 8);
+        plain.screen(Vec2i(0, 0)).bitblt(&tex, FULL, Vec2i(tx, ty));
+        let mut transformed = Offscreen::new(8, 8);
+        transformed.screen(Vec2i(0, 0)).bitblt_transformed(
+            &tex,
+            FULL,
+            Vec2i(tx, ty),
+            identity,
+            DrawParams::default(),
+        );
+        assert_eq!(plain.buffer(), transformed.buffer());
+    }
+}
+
+#[test]
+fn bitblt_transformed_quarter_turn() {
+    let tex = gradient_texture(4, 4);
+    let mut offscreen = Offscreen::new(8, 8);
+    offscreen.screen(Vec2i(0, 0)).bitblt_transformed(
+        &tex,
+        FULL,
+        Vec2i(2, 2),
+        Transform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: (1.0, 1.0),
+            pivot: (2.0, 2.0),
+        },
+        DrawParams::default(),
+    );
+    // A clockwise quarter turn sends the source's left column to the top row
+    for y in 0..4 {
+        for x in 0..4 {
+            let (sx, sy) = (y, 3 - x);
+            assert_eq!(
+                pixel(&offscreen, x as usize + 2, y as usize + 2),
+                [(sx * 16) as u8, (sy * 16) as u8, 200, 255]
+            );
+        }
+    }
+}
+
+#[test]
+fn rotated_scaled_sprites_match_golden() {
+    let mut offscreen = Offscreen::new(240, 240);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    let mut sprites = level_1(&mut Assets::new()).unwrap();
+    // Asteroids start at index 6; spin them by increasing amounts and scale every other one
+    for (i, s) in sprites.iter_mut().enumerate().skip(6) {
+        s.rotation = i as f32 * 0.4;
+        if i % 2 == 0 {
+            s.scale = (1.5, 0.75);
+        }
+    }
+    for s in sprites.iter_mut().rev() {
+        screen.draw_sprite(s);
+    }
+    check_golden("llama_level_1_rotated", &offscreen, TOLERANCE);
+}
+
+fn assert_close(got: [u8; 4], want: [u8; 4]) {
+    assert!(
+        got.iter()
+            .zip(want.iter())
+            .all(|(g, w)| (*g as i16 - *w as i16).abs() <= TOLERANCE as i16),
+        "expected {:?}, got {:?}",
+        want,
+        got
+    );
+}
+
+#[test]
+fn bitblt_composites_premultiplied_over() {
+    let mut offscreen = Offscreen::new(4, 1);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 255, 255));
+    // opaque, fully transparent, and half transparent red
+    screen.bitblt(&solid_texture(1, 1, [255, 0, 0, 255]), FULL_1, Vec2i(0, 0));
+    screen.bitblt(&solid_texture(1, 1, [255, 0, 0, 0]), FULL_1, Vec2i(1, 0));
+    screen.bitblt(&solid_texture(1, 1, [255, 0, 0, 128]), FULL_1, Vec2i(2, 0));
+    assert_eq!(pixel(&offscreen, 0, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(&offscreen, 1, 0), [0, 0, 255, 255]);
+    assert_close(pixel(&offscreen, 2, 0), [128, 0, 127, 255]);
+    assert_eq!(pixel(&offscreen, 3, 0), [0, 0, 255, 255]);
+}
+
+#[test]
+fn bitblt_composites_onto_transparent() {
+    let mut offscreen = Offscreen::new(1, 1);
+    offscreen.screen(Vec2i(0, 0)).bitblt(
+        &solid_texture(1, 1, [0, 255, 0, 128]),
+        FULL_1,
+        Vec2i(0, 0),
+    );
+    assert_close(pixel(&offscreen, 0, 0), [0, 128, 0, 128]);
+}
+
+#[test]
+fn bitblt_tints_and_fades() {
+    let tex = solid_texture(1, 1, [200, 100, 50, 255]);
+    let mut offscreen = Offscreen::new(4, 1);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    let tinted = DrawParams {
+        tint: Rgba(255, 128, 0, 255),
+        ..DrawParams::default()
+    };
+    let faded = DrawParams {
+        opacity: 0.5,
+        ..DrawParams::default()
+    };
+    let invisible = DrawParams {
+        opacity: 0.0,
+        ..DrawParams::default()
+    };
+    let see_through_tint = DrawParams {
+        tint: Rgba(255, 255, 255, 128),
+        ..DrawParams::default()
+    };
+    screen.bitblt_with(&tex, FULL_1, Vec2i(0, 0), tinted);
+    screen.bitblt_with(&tex, FULL_1, Vec2i(1, 0), faded);
+    screen.bitblt_with(&tex, FULL_1, Vec2i(2, 0), invisible);
+    screen.bitblt_with(&tex, FULL_1, Vec2i(3, 0), see_through_tint);
+    assert_close(pixel(&offscreen, 0, 0), [200, 50, 0, 255]);
+    assert_close(pixel(&offscreen, 1, 0), [100, 50, 25, 255]);
+    assert_eq!(pixel(&offscreen, 2, 0), [0, 0, 0, 255]);
+    assert_close(pixel(&offscreen, 3, 0), [100, 50, 25, 255]);
+}
+
+// Draw `src` (straight alpha) over an opaque `dst` with the given blend mode
+fn blend_one(mode: BlendMode, dst: Rgba, src: [u8; 4]) -> [u8; 4] {
+    let mut offscreen = Offscreen::new(1, 1);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(dst);
+    screen.bitblt_with(
+        &solid_texture(1, 1, src),
+        FULL_1,
+        Vec2i(0, 0),
+        DrawParams {
+            blend: mode,
+            ..DrawParams::default()
+        },
+    );
+    pixel(&offscreen, 0, 0)
+}
+
+#[test]
+fn bitblt_blend_modes() {
+    let dst = Rgba(100, 200, 50, 255);
+    let src = [200, 100, 255, 255];
+    assert_eq!(blend_one(BlendMode::Over, dst, src), [200, 100, 255, 255]);
+    assert_eq!(blend_one(BlendMode::Copy, dst, src), [200, 100, 255, 255]);
+    assert_eq!(
+        blend_one(BlendMode::Additive, dst, src),
+        [255, 255, 255, 255]
+    );
+    assert_close(blend_one(BlendMode::Multiply, dst, src), [78, 78, 50, 255]);
+    assert_close(blend_one(BlendMode::Screen, dst, src), [222, 222, 255, 255]);
+    // Fully transparent sources leave the destination alone, except for a copy
+    let clear = [0, 0, 0, 0];
+    for mode in &[
+        BlendMode::Over,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ] {
+        assert_eq!(blend_one(*mode, dst, clear), [100, 200, 50, 255]);
+    }
+    assert_eq!(blend_one(BlendMode::Copy, dst, clear), [0, 0, 0, 0]);
+}
+
+#[test]
+fn opaque_copy_matches_over() {
+    // Copying is only the same as compositing if every tile pixel is opaque
+    let mut assets = Assets::new();
+    let tiles = assets.texture(Path::new("slug/slug_tiles.png")).unwrap();
+    assert!(tiles.buffer().chunks_exact(4).all(|px| px[3] == 255));
+    // Anything see-through would let this show through one and not the other
+    let backdrop = Rgba(30, 160, 90, 255);
+    let mut over = Offscreen::new(480, 480);
+    let mut screen = over.screen(Vec2i(1, 1));
+    screen.clear(backdrop);
+    reset_tiles(&mut assets).unwrap().draw(&mut screen);
+    let mut copied = Offscreen::new(480, 480);
+    let mut screen = copied.screen(Vec2i(1, 1));
+    screen.clear(backdrop);
+    reset_tiles(&mut assets).unwrap().draw_with(
+        &mut screen,
+        DrawParams {
+            blend: BlendMode::Copy,
+            ..DrawParams::default()
+        },
+    );
+    assert_eq!(over.buffer(), copied.buffer());
+}
+
+#[test]
+fn primitives_match_golden() {
+    let mut offscreen = Offscreen::new(64, 64);
+    // Offset camera, so all of these are shifted 4px up and left
+    let mut screen = offscreen.screen(Vec2i(4, 4));
+    screen.clear(Rgba(0, 0, 0, 255));
+    screen.fill_rect(
+        Rect {
+            x: 0,
+            y: 0,
+            w: 20,
+            h: 12,
+        },
+        Rgba(255, 0, 0, 255),
+    );
+    screen.draw_rect(
+        Rect {
+            x: 10,
+            y: 8,
+            w: 30,
+            h: 20,
+        },
+        Rgba(0, 255, 0, 128),
+    );
+    screen.line(Vec2i(4, 60), Vec2i(70, 30), Rgba(255, 255, 0, 255));
+    screen.line(Vec2i(50, 4), Vec2i(45, 40), Rgba(0, 255, 255, 255));
+    screen.fill_circle(Vec2i(50, 50), 10, Rgba(0, 0, 255, 160));
+    screen.draw_circle(Vec2i(66, 10), 9, Rgba(255, 255, 255, 255));
+    screen.put_pixel(Vec2i(30, 40), Rgba(255, 0, 255, 255));
+    check_golden("primitives", &offscreen, TOLERANCE);
+}
+
+#[test]
+fn primitives_clip_and_respect_camera() {
+    let mut offscreen = Offscreen::new(8, 8);
+    let mut screen = offscreen.screen(Vec2i(2, 2));
+    let white = Rgba(255, 255, 255, 255);
+    screen.fill_rect(
+        Rect {
+            x: -10,
+            y: 6,
+            w: 100,
+            h: 100,
+        },
+        white,
+    );
+    // Way off screen in every direction; none of these should panic or draw
+    screen.line(Vec2i(-100, -100), Vec2i(-50, 500), white);
+    screen.fill_circle(Vec2i(-20, -20), 5, white);
+    screen.draw_circle(Vec2i(100, 3), 5, white);
+    screen.put_pixel(Vec2i(10, 4), white);
+    screen.put_pixel(Vec2i(9, 4), white);
+    for y in 0..8 {
+        for x in 0..8 {
+            let lit = y >= 4 || (x, y) == (7, 2);
+            let want = if lit {
+                [255, 255, 255, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            assert_eq!(pixel(&offscreen, x, y), want, "at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn line_hits_both_ends() {
+    let mut offscreen = Offscreen::new(8, 8);
+    offscreen
+        .screen(Vec2i(0, 0))
+        .line(Vec2i(6, 1), Vec2i(1, 5), Rgba(255, 255, 255, 255));
+    let lit: Vec<(usize, usize)> = (0..64)
+        .map(|i| (i % 8, i / 8))
+        .filter(|&(x, y)| pixel(&offscreen, x, y)[3] != 0)
+        .collect();
+    assert!(lit.contains(&(6, 1)));
+    assert!(lit.contains(&(1, 5)));
+    // One pixel per step along the major (x) axis
+    assert_eq!(lit.len(), 6);
+}
+
+#[test]
+fn huge_circles_cover_the_screen() {
+    let mut offscreen = Offscreen::new(16, 16);
+    let mut dirty = DirtyRects::new();
+    let white = Rgba(255, 255, 255, 255);
+    dirty.draw(&mut offscreen.screen(Vec2i(0, 0)), |screen| {
+        screen.clear(Rgba(0, 0, 0, 255));
+    });
+    let changed = dirty.draw(&mut offscreen.screen(Vec2i(0, 0)), |screen| {
+        screen.clear(Rgba(0, 0, 0, 255));
+        screen.fill_circle(Vec2i(8, 8), 50000, white);
+        screen.draw_circle(Vec2i(8, 8), 50000, white);
+    });
+    assert!(changed);
+    assert_eq!(
+        dirty.redrawn(),
+        &[Rect {
+            x: 0,
+            y: 0,
+            w: 16,
+            h: 16
+        }]
+    );
+    assert!(offscreen.buffer().iter().all(|&b| b == 255));
+}
+
+#[test]
+fn long_lines_only_step_through_the_screen() {
+    let mut offscreen = Offscreen::new(8, 8);
+    let far = 1_000_000_000;
+    // Billions of steps long, so this would take a while if the off screen ones weren't skipped
+    offscreen.screen(Vec2i(0, 0)).line(
+        Vec2i(-far, -far),
+        Vec2i(far, far),
+        Rgba(255, 255, 255, 255),
+    );
+    for y in 0..8 {
+        for x in 0..8 {
+            assert_eq!(pixel(&offscreen, x, y)[3] != 0, x == y, "at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn clipped_lines_land_on_the_same_pixels() {
+    let white = Rgba(255, 255, 255, 255);
+    let lines = [
+        (Vec2i(-30, 3), Vec2i(40, 20)),
+        (Vec2i(37, -9), Vec2i(2, 33)),
+        (Vec2i(5, 30), Vec2i(9, -4)),
+    ];
+    let mut whole = Offscreen::new(32, 24);
+    for &(from, to) in lines.iter() {
+        whole.screen(Vec2i(0, 0)).line(from, to, white);
+    }
+    let clip = Rect {
+        x: 7,
+        y: 5,
+        w: 13,
+        h: 11,
+    };
+    let mut clipped = Offscreen::new(32, 24);
+    for &(from, to) in lines.iter() {
+        let mut screen = clipped.screen(Vec2i(0, 0));
+        screen.set_clip(clip);
+        screen.line(from, to, white);
+    }
+    for y in 0..24 {
+        for x in 0..32 {
+            let inside = (7..20).contains(&x) && (5..16).contains(&y);
+            let want = if inside { pixel(&whole, x, y) } else { [0; 4] };
+            assert_eq!(pixel(&clipped, x, y), want, "at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn hud_ignores_camera_and_restores_it() {
+    let tex = gradient_texture(4, 4);
+    let mut expected = Offscreen::new(8, 8);
+    expected.screen(Vec2i(0, 0)).bitblt(&tex, FULL, Vec2i(2, 3));
+    let mut offscreen = Offscreen::new(8, 8);
+    let mut screen = offscreen.screen(Vec2i(-5, 17));
+    screen.hud(|screen| screen.bitblt(&tex, FULL, Vec2i(2, 3)));
+    assert_eq!(screen.position, Vec2i(-5, 17));
+    assert_eq!(offscreen.buffer(), expected.buffer());
+}
+
+#[test]
+fn hud_sprites_ignore_camera() {
+    let tex = Rc::new(gradient_texture(4, 4));
+    let animation = Animation::new(vec![AnimationState {
+        frames: vec![FULL],
+        current_index: 0,
+        start_time: 0,
+        repeat: true,
+    }]);
+    let mut sprite =
+        Sprite::new(&tex, animation, Vec2i(1, 2), vec![], false, false, false).unwrap();
+    sprite.hud = true;
+    for &camera in &[Vec2i(0, 0), Vec2i(3, -2), Vec2i(-40, 90)] {
+        let mut offscreen = Offscreen::new(8, 8);
+        offscreen.screen(camera).draw_sprite(&sprite);
+        for y in 0..8 {
+            for x in 0..8 {
+                let want = match inside(x, y, 1, 2, FULL) {
+                    Some((sx, sy)) => [(sx * 16) as u8, (sy * 16) as u8, 200, 255],
+                    None => [0, 0, 0, 0],
+                };
+                let got = pixel(&offscreen, x as usize, y as usize);
+                assert_eq!(got, want, "at ({}, {}) with camera {:?}", x, y, camera);
+            }
+        }
+    }
+}
+
+// The blends as they were first written, in floating point, to check the integer versions against
+fn float_blend(mode: BlendMode, to: [u8; 4], from: [u8; 4]) -> [u8; 4] {
+    let ta = to[3] as f32 / 255.0;
+    let fa = from[3] as f32 / 255.0;
+    let alpha = ((fa + ta * (1.0 - fa)) * 255.0).round() as u8;
+    let mut out = [0, 0, 0, alpha];
+    for i in 0..3 {
+        let (t, f) = (to[i] as f32, from[i] as f32);
+        out[i] = match mode {
+            BlendMode::Over => from[i].saturating_add((t * (1.0 - fa)).round() as u8),
+            BlendMode::Multiply => (t * f / 255.0 + f * (1.0 - ta) + t * (1.0 - fa))
+                .round()
+                .min(255.0) as u8,
+            BlendMode::Screen => (t + f - t * f / 255.0).round().min(255.0) as u8,
+            _ => unreachable!(),
+        };
+    }
+    out
+}
+
+fn float_modulate(from: [u8; 4], params: DrawParams) -> [u8; 4] {
+    let Rgba(r, g, b, a) = params.tint;
+    let alpha = (a as f32 / 255.0) * params.opacity;
+    let mul = [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0];
+    let mut out = [0; 4];
+    for i in 0..4 {
+        out[i] = (from[i] as f32 * mul[i] * alpha).round() as u8;
+    }
+    out
+}
+
+#[test]
+fn integer_compositing_matches_float_reference() {
+    let (w, h) = (64, 32);
+    let mut rng = StdRng::seed_from_u64(21);
+    // Random premultiplied pixels, with plenty of the fully transparent and opaque ones sprites are made of
+    let random_pixels = |rng: &mut StdRng| -> Vec<u8> {
+        (0..w * h)
+            .flat_map(|_| {
+                let a = match rng.gen_range(0, 4) {
+                    0 => 0,
+                    1 => 255,
+                    _ => rng.gen_range(0, 256) as u8,
+                };
+                let mut c = || rng.gen_range(0, a as u32 + 1) as u8;
+                vec![c(), c(), c(), a]
+            })
+            .collect()
+    };
+    let whole = Rect {
+        x: 0,
+        y: 0,
+        w: w as u16,
+        h: h as u16,
+    };
+    for &mode in &[BlendMode::Over, BlendMode::Multiply, BlendMode::Screen] {
+        let variants = [
+            DrawParams::default(),
+            DrawParams {
+                flip_x: true,
+                ..DrawParams::default()
+            },
+            DrawParams {
+                tint: Rgba(255, 128, 30, 200),
+                opacity: 0.7,
+                ..DrawParams::default()
+            },
+        ];
+        for &params in variants.iter() {
+            let params = DrawParams {
+                blend: mode,
+                ..params
+            };
+            let dst = random_pixels(&mut rng);
+            let src = random_pixels(&mut rng);
+            let mut offscreen = Offscreen::new(w, h);
+            let mut screen = offscreen.screen(Vec2i(0, 0));
+            let copy = DrawParams {
+                blend: BlendMode::Copy,
+                ..DrawParams::default()
+            };
+            let dst_tex = Texture::from_premultiplied(w, h, dst.clone());
+            screen.bitblt_with(&dst_tex, whole, Vec2i(0, 0), copy);
+            let src_tex = Texture::from_premultiplied(w, h, src.clone());
+            screen.bitblt_with(&src_tex, whole, Vec2i(0, 0), params);
+            for y in 0..h {
+                for x in 0..w {
+                    let sx = if params.flip_x { w - 1 - x } else { x };
+                    let at = |buf: &[u8], x: usize| {
+                        let i = (y * w + x) * 4;
+                        [buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]
+                    };
+                    let from = float_modulate(at(&src, sx), params);
+                    let want = float_blend(mode, at(&dst, x), from);
+                    assert_close(pixel(&offscreen, x, y), want);
+                }
+            }
+        }
+    }
+}
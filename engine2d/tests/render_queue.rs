@@ -0,0 +1,86 @@
+mod common;
+
+use common::*;
+use engine2d::animation::Animation;
+use engine2d::offscreen::Offscreen;
+use engine2d::render_queue::{Layer, RenderQueue};
+use engine2d::screen::Screen;
+use engine2d::sprite::Sprite;
+use engine2d::texture::Texture;
+use engine2d::types::{AnimationState, Rect, Vec2i};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const WHOLE: Rect = Rect {
+    x: 0,
+    y: 0,
+    w: 4,
+    h: 4,
+};
+
+#[test]
+fn draws_by_layer_then_depth_then_order() {
+    let order = RefCell::new(vec![]);
+    let mut queue = RenderQueue::new();
+    let log = |name: &'static str| {
+        let order = &order;
+        move |_: &mut Screen| order.borrow_mut().push(name)
+    };
+    queue.custom(Layer::Hud, 0, log("hud"));
+    queue.custom(Layer::World, 5, log("front"));
+    queue.custom(Layer::World, -2, log("back"));
+    queue.custom(Layer::Background, 100, log("sky"));
+    queue.custom(Layer::World, 5, log("front again"));
+    queue.custom(Layer::Effects, -50, log("sparks"));
+    assert_eq!(queue.len(), 6);
+    let mut offscreen = Offscreen::new(4, 4);
+    queue.draw(&mut offscreen.screen(Vec2i(0, 0)));
+    assert_eq!(
+        *order.borrow(),
+        vec!["sky", "back", "front", "front again", "sparks", "hud"]
+    );
+}
+
+fn sprite(tex: &Rc<Texture>, position: Vec2i) -> Sprite {
+    let animation = Animation::new(vec![AnimationState {
+        frames: vec![WHOLE],
+        current_index: 0,
+        start_time: 0,
+        repeat: true,
+    }]);
+    Sprite::new(tex, animation, position, vec![], false, false, false).unwrap()
+}
+
+#[test]
+fn sprites_sort_whatever_order_they_are_in() {
+    let red = Rc::new(solid_texture(4, 4, [255, 0, 0, 255]));
+    let blue = Rc::new(solid_texture(4, 4, [0, 0, 255, 255]));
+    let sprites = [sprite(&red, Vec2i(0, 0)), sprite(&blue, Vec2i(2, 2))];
+    for &(red_depth, want) in &[(1, [255, 0, 0, 255]), (-1, [0, 0, 255, 255])] {
+        let mut queue = RenderQueue::new();
+        queue.sprite(Layer::World, red_depth, &sprites[0]);
+        queue.sprite(Layer::World, 0, &sprites[1]);
+        let mut offscreen = Offscreen::new(8, 8);
+        queue.draw(&mut offscreen.screen(Vec2i(0, 0)));
+        // Where they overlap
+        assert_eq!(pixel(&offscreen, 3, 3), want);
+        assert_eq!(pixel(&offscreen, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&offscreen, 5, 5), [0, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn hud_layer_ignores_camera() {
+    let tex = gradient_texture(4, 4);
+    let mut queue = RenderQueue::new();
+    queue.custom(Layer::Hud, 0, |screen| {
+        screen.bitblt(&tex, WHOLE, Vec2i(1, 1))
+    });
+    let mut offscreen = Offscreen::new(8, 8);
+    let mut screen = offscreen.screen(Vec2i(40, -9));
+    queue.draw(&mut screen);
+    assert_eq!(screen.position, Vec2i(40, -9));
+    assert_eq!(pixel(&offscreen, 1, 1), [0, 0, 200, 255]);
+    assert_eq!(pixel(&offscreen, 4, 4), [48, 48, 200, 255]);
+    assert_eq!(pixel(&offscreen, 0, 0), [0, 0, 0, 0]);
+}
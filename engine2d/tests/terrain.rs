@@ -0,0 +1,164 @@
+mod common;
+
+use common::*;
+use engine2d::assets::Assets;
+use engine2d::collision::terrain_contact;
+use engine2d::llama_maker::{make_rock, make_ship};
+use engine2d::offscreen::Offscreen;
+use engine2d::slug_maker::{make_mud, make_player, reset_tiles};
+use engine2d::terrain::Terrain;
+use engine2d::texture::Texture;
+use engine2d::types::{Rect, Rgba, Vec2i};
+use image::RgbaImage;
+
+// A 10x10 block of solid red at (100, 50), with a see-through top row
+fn block() -> Terrain {
+    let image = RgbaImage::from_fn(10, 10, |_, y| {
+        let alpha = if y == 0 { 40 } else { 255 };
+        image::Rgba([255, 0, 0, alpha])
+    });
+    Terrain::new(Texture::new(image), Vec2i(100, 50))
+}
+
+#[test]
+fn solid_where_opaque() {
+    let terrain = block();
+    assert_eq!(terrain.solid_count(), 90);
+    assert!(terrain.is_solid(Vec2i(100, 51)));
+    assert!(terrain.is_solid(Vec2i(109, 59)));
+    assert!(!terrain.is_solid(Vec2i(104, 50)));
+    assert!(!terrain.is_solid(Vec2i(99, 55)));
+    assert!(!terrain.is_solid(Vec2i(110, 55)));
+}
+
+#[test]
+fn first_solid_is_leftmost_then_topmost() {
+    let terrain = block();
+    let area = Rect {
+        x: 95,
+        y: 45,
+        w: 20,
+        h: 20,
+    };
+    assert_eq!(terrain.first_solid(area), Some(Vec2i(100, 51)));
+    assert!(terrain.overlaps(area));
+    let above = Rect {
+        x: 100,
+        y: 40,
+        w: 10,
+        h: 11,
+    };
+    assert!(!terrain.overlaps(above));
+}
+
+#[test]
+fn carving_a_circle_clears_pixels_and_the_mask() {
+    let mut terrain = block();
+    let carved = terrain.carve_circle(Vec2i(105, 55), 2);
+    // Same shape as a filled circle of radius 2: rows of 1, 3, 5, 3, 1
+    assert_eq!(carved, 13);
+    assert_eq!(terrain.solid_count(), 90 - 13);
+    assert!(!terrain.is_solid(Vec2i(105, 55)));
+    assert!(!terrain.is_solid(Vec2i(103, 55)));
+    assert!(terrain.is_solid(Vec2i(102, 55)));
+    let mut offscreen = Offscreen::new(20, 20);
+    terrain.draw(&mut offscreen.screen(Vec2i(95, 45)));
+    assert_eq!(pixel(&offscreen, 10, 10), [0, 0, 0, 0]);
+    assert_eq!(pixel(&offscreen, 7, 10), [255, 0, 0, 255]);
+    // Carving the same place again doesn't take anything more
+    assert_eq!(terrain.carve_circle(Vec2i(105, 55), 2), 0);
+}
+
+#[test]
+fn carving_off_the_edge_is_fine() {
+    let mut terrain = block();
+    assert_eq!(terrain.carve_circle(Vec2i(0, 0), 5), 0);
+    assert_eq!(terrain.carve_circle(Vec2i(100, 59), 1), 3);
+}
+
+#[test]
+fn huge_and_empty_carves_are_fine() {
+    let mut terrain = block();
+    assert_eq!(terrain.carve_circle(Vec2i(105, 55), 50000), 90);
+    assert_eq!(terrain.solid_count(), 0);
+    let mut empty = Terrain::new(Texture::blank(0, 0), Vec2i(0, 0));
+    assert_eq!(empty.carve_circle(Vec2i(0, 0), 3), 0);
+    assert_eq!(
+        empty.carve_mask(&solid_texture(2, 2, [0, 0, 0, 255]), Vec2i(0, 0)),
+        0
+    );
+    assert!(!empty.is_solid(Vec2i(0, 0)));
+}
+
+#[test]
+fn masks_carve_by_how_opaque_they_are() {
+    let mut terrain = block();
+    // The left half of the mask takes everything, the right half only takes a quarter
+    let mask = Texture::new(RgbaImage::from_fn(4, 2, |x, _| {
+        let alpha = if x < 2 { 255 } else { 64 };
+        image::Rgba([0, 0, 0, alpha])
+    }));
+    let carved = terrain.carve_mask(&mask, Vec2i(102, 54));
+    assert_eq!(carved, 4);
+    assert!(!terrain.is_solid(Vec2i(103, 55)));
+    assert!(terrain.is_solid(Vec2i(104, 55)));
+    let mut offscreen = Offscreen::new(10, 10);
+    terrain.draw(&mut offscreen.screen(Vec2i(100, 50)));
+    assert_eq!(pixel(&offscreen, 2, 4), [0, 0, 0, 0]);
+    assert_eq!(pixel(&offscreen, 4, 4), [191, 0, 0, 191]);
+    // Two more and it's see-through enough not to be solid
+    assert_eq!(terrain.carve_mask(&mask, Vec2i(102, 54)), 0);
+    assert_eq!(terrain.carve_mask(&mask, Vec2i(102, 54)), 4);
+}
+
+#[test]
+fn sprites_run_into_what_is_left() {
+    let mut assets = Assets::new();
+    let mut rock = make_rock(&mut assets, Vec2i(0, 0)).unwrap();
+    let mut ship = make_ship(&mut assets).unwrap();
+    assert_eq!(rock.size(), (64, 64));
+    let middle = Vec2i(32, 32);
+    assert!(rock.is_solid(middle));
+    ship.hit_boxes = vec![Rect {
+        x: 30,
+        y: 30,
+        w: 4,
+        h: 4,
+    }];
+    assert!(terrain_contact(&ship, &rock));
+    rock.carve_circle(middle, 6);
+    assert!(!terrain_contact(&ship, &rock));
+}
+
+#[test]
+fn slug_digs_through_mud() {
+    let mut assets = Assets::new();
+    let mut mud = make_mud(&mut assets).unwrap();
+    let tiles = reset_tiles(&mut assets).unwrap();
+    // It's solid all the way through and only covers floor, so it can always be dug out of
+    let bounds = mud.bounds();
+    assert_eq!(mud.solid_count(), bounds.w as usize * bounds.h as usize);
+    for y in (bounds.y..bounds.y + bounds.h as i32).step_by(48) {
+        assert_eq!(tiles.tile_id_at(Vec2i(bounds.x, y)).0, 1);
+    }
+    let mut slug = make_player(&mut assets).unwrap();
+    slug.hit_boxes[0].x = bounds.x;
+    slug.hit_boxes[0].y = bounds.y + 100;
+    assert!(terrain_contact(&slug, &mud));
+    let Rect { x, y, w, h } = slug.hit_boxes[0];
+    mud.carve_circle(Vec2i(x + w as i32 / 2, y + h as i32 / 2), 12);
+    assert!(!terrain_contact(&slug, &mud));
+}
+
+#[test]
+fn carving_shows_up_on_screen() {
+    let mut terrain = block();
+    let id = terrain.texture().id();
+    terrain.carve_circle(Vec2i(105, 55), 1);
+    assert_ne!(terrain.texture().id(), id);
+    let mut offscreen = Offscreen::new(10, 10);
+    let mut screen = offscreen.screen(Vec2i(100, 50));
+    screen.clear(Rgba(0, 0, 255, 255));
+    terrain.draw(&mut screen);
+    assert_eq!(pixel(&offscreen, 5, 5), [0, 0, 255, 255]);
+}
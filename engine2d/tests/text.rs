@@ -0,0 +1,155 @@
+mod common;
+
+use common::*;
+use engine2d::font::{Align, BitmapFont, TextStyle};
+use engine2d::offscreen::Offscreen;
+use engine2d::texture::Texture;
+use engine2d::truetype::{GlyphCache, TrueTypeFont};
+use engine2d::types::{Rgba, Vec2i};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+fn font() -> BitmapFont {
+    let tex = Rc::new(Texture::with_file(Path::new("fonts/font5x7.png")).unwrap());
+    let metrics = fs::read_to_string("fonts/font5x7.fnt").unwrap();
+    BitmapFont::from_bmfont(&tex, &metrics).unwrap()
+}
+
+#[test]
+fn measures_with_kerning() {
+    let font = font();
+    assert_eq!(font.line_width("AB"), 12);
+    // L and T are kerned together by a pixel
+    assert_eq!(font.line_width("LT"), 11);
+    assert_eq!(font.measure("AB\nLONGER", None), (36, 18));
+}
+
+#[test]
+fn wraps_between_words() {
+    let font = font();
+    assert_eq!(
+        font.layout("THE QUICK BROWN FOX\nJUMPS", Some(60)),
+        vec!["THE QUICK", "BROWN FOX", "JUMPS"]
+    );
+    // A word too long for the line still gets drawn, on its own line
+    assert_eq!(
+        font.layout("A SUPERCALIFRAGILISTIC B", Some(30)),
+        vec!["A", "SUPERCALIFRAGILISTIC", "B"]
+    );
+    assert_eq!(font.layout("", Some(30)), vec![""]);
+}
+
+#[test]
+fn bad_metrics_report_the_line() {
+    let tex = Rc::new(solid_texture(8, 8, [255, 255, 255, 255]));
+    let err = BitmapFont::from_bmfont(
+        &tex,
+        "common lineHeight=9\nchar id=65 x=0 y=0 width=five height=7 xoffset=0 yoffset=0 xadvance=6",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.line, 2);
+    assert!(BitmapFont::from_bmfont(&tex, "char id=65 x=0 y=0").is_err());
+}
+
+#[test]
+fn glyphs_outside_the_texture_are_rejected() {
+    let tex = Rc::new(solid_texture(8, 8, [255, 255, 255, 255]));
+    let fits =
+        "common lineHeight=9\nchar id=65 x=3 y=1 width=5 height=7 xoffset=0 yoffset=0 xadvance=6";
+    assert!(BitmapFont::from_bmfont(&tex, fits).is_ok());
+    // One pixel past the right edge
+    let err = BitmapFont::from_bmfont(
+        &tex,
+        "common lineHeight=9\nchar id=65 x=4 y=1 width=5 height=7 xoffset=0 yoffset=0 xadvance=6",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.line, 2);
+    assert!(err.message.contains("8x8"), "{}", err.message);
+    let huge = "char id=65 x=0 y=0 width=70000 height=7 xoffset=0 yoffset=0 xadvance=6";
+    assert!(BitmapFont::from_bmfont(&tex, huge).is_err());
+}
+
+#[test]
+fn text_matches_golden() {
+    let font = font();
+    let mut offscreen = Offscreen::new(96, 64);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(Rgba(0, 0, 0, 255));
+    font.draw(&mut screen, "Score: 42", Vec2i(2, 2), TextStyle::default());
+    font.draw(
+        &mut screen,
+        "LATE TO THE PARTY",
+        Vec2i(2, 14),
+        TextStyle {
+            color: Rgba(255, 200, 0, 255),
+            align: Align::Center,
+            wrap: Some(60),
+        },
+    );
+    font.draw(
+        &mut screen,
+        "RIGHT\nSIDE",
+        Vec2i(94, 40),
+        TextStyle {
+            color: Rgba(0, 255, 128, 255),
+            align: Align::Right,
+            wrap: None,
+        },
+    );
+    check_golden("text", &offscreen, TOLERANCE);
+}
+
+// DejaVu Sans, cut down to ASCII to keep it small
+fn test_font() -> Rc<TrueTypeFont> {
+    Rc::new(TrueTypeFont::with_file(Path::new("fonts/DejaVuSans-ASCII.ttf")).unwrap())
+}
+
+#[test]
+fn truetype_rejects_garbage() {
+    assert!(TrueTypeFont::from_bytes(b"definitely not a font").is_err());
+    // Whatever went wrong with a file, the message says which one
+    for path in &["fonts/missing.ttf", "fonts/font5x7.png"] {
+        match TrueTypeFont::with_file(Path::new(path)) {
+            Err(e) => assert!(e.to_string().contains(path), "{}", e),
+            Ok(_) => panic!("loaded {} as a font", path),
+        }
+    }
+}
+
+#[test]
+fn glyph_cache_rasterizes_once() {
+    let font = test_font();
+    let mut cache = GlyphCache::new(&font, 16.0);
+    cache.prepare("Hello");
+    let texture = Rc::clone(&cache.font().texture);
+    // Nothing new, so the atlas is left alone
+    cache.prepare("lolHe");
+    assert!(Rc::ptr_eq(&texture, &cache.font().texture));
+    cache.prepare("World ");
+    assert!(!Rc::ptr_eq(&texture, &cache.font().texture));
+    let (w, h) = cache.font().measure("Hello World", None);
+    assert!(w > 60 && w < 120, "unexpected width {}", w);
+    assert_eq!(h, cache.font().line_height);
+}
+
+#[test]
+fn glyph_cache_draws_text() {
+    let font = test_font();
+    let mut cache = GlyphCache::new(&font, 24.0);
+    let mut offscreen = Offscreen::new(64, 32);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    cache.draw(&mut screen, "Ag", Vec2i(2, 2), TextStyle::default());
+    let lit: Vec<(usize, usize)> = (0..64 * 32)
+        .map(|i| (i % 64, i / 64))
+        .filter(|&(x, y)| pixel(&offscreen, x, y)[3] > 128)
+        .collect();
+    assert!(!lit.is_empty());
+    // Everything lands inside the line, and the g's descender dips below the A
+    let bottom_of_a = lit.iter().filter(|p| p.0 < 18).map(|p| p.1).max().unwrap();
+    let bottom_of_g = lit.iter().map(|p| p.1).max().unwrap();
+    assert!(bottom_of_g > bottom_of_a);
+    assert!(bottom_of_g < 2 + cache.font().line_height as usize);
+}
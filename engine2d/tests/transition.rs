@@ -0,0 +1,198 @@
+mod common;
+
+use common::*;
+use engine2d::dirty::DirtyRects;
+use engine2d::offscreen::Offscreen;
+use engine2d::screen::Screen;
+use engine2d::transition::{Effect, Transition};
+use engine2d::types::{Rect, Rgba, Vec2i};
+
+const OLD: Rgba = Rgba(200, 40, 40, 255);
+const NEW: Rgba = Rgba(40, 40, 200, 255);
+const BLACK: Rgba = Rgba(0, 0, 0, 255);
+
+fn px(Rgba(r, g, b, a): Rgba) -> [u8; 4] {
+    [r, g, b, a]
+}
+
+// Start `effect` from a screen full of OLD, run it for `frames` of `duration`,
+// then draw it over a screen full of NEW
+fn run(effect: Effect, duration: usize, frames: usize) -> Offscreen {
+    let mut offscreen = Offscreen::new(16, 8);
+    // The camera shouldn't matter, transitions are drawn in screen space
+    let mut screen = offscreen.screen(Vec2i(30, -7));
+    screen.clear(OLD);
+    let mut transition = Transition::new(effect, duration);
+    transition.capture(&screen);
+    screen.clear(NEW);
+    // Capturing again mustn't replace the old scene
+    transition.capture(&screen);
+    for _ in 0..frames {
+        transition.update();
+    }
+    transition.draw(&mut screen);
+    offscreen
+}
+
+fn check_all(offscreen: &Offscreen, want: [u8; 4]) {
+    for y in 0..8 {
+        for x in 0..16 {
+            assert_close(pixel(offscreen, x, y), want, x, y);
+        }
+    }
+}
+
+fn assert_close(got: [u8; 4], want: [u8; 4], x: usize, y: usize) {
+    for c in 0..4 {
+        assert!(
+            (got[c] as i32 - want[c] as i32).abs() <= TOLERANCE as i32,
+            "at ({}, {}) got {:?}, want {:?}",
+            x,
+            y,
+            got,
+            want
+        );
+    }
+}
+
+#[test]
+fn runs_for_its_duration() {
+    let mut transition = Transition::new(Effect::Crossfade, 4);
+    assert!(!transition.done());
+    for _ in 0..3 {
+        transition.update();
+    }
+    assert_eq!(transition.progress(), 0.75);
+    assert!(!transition.done());
+    transition.update();
+    assert!(transition.done());
+    transition.update();
+    assert_eq!(transition.progress(), 1.0);
+    assert!(Transition::new(Effect::Wipe, 0).done());
+}
+
+#[test]
+fn fade_goes_out_then_in() {
+    check_all(&run(Effect::Fade(BLACK), 10, 0), px(OLD));
+    check_all(&run(Effect::Fade(BLACK), 10, 5), px(BLACK));
+    check_all(&run(Effect::Fade(BLACK), 10, 10), px(NEW));
+    // A quarter of the way, the old scene is half faded out
+    check_all(&run(Effect::Fade(BLACK), 8, 2), [100, 20, 20, 255]);
+    // Three quarters, the new scene is half faded in
+    check_all(&run(Effect::Fade(BLACK), 8, 6), [20, 20, 100, 255]);
+}
+
+#[test]
+fn crossfade_blends_old_over_new() {
+    check_all(&run(Effect::Crossfade, 10, 0), px(OLD));
+    check_all(&run(Effect::Crossfade, 10, 5), [120, 40, 120, 255]);
+    check_all(&run(Effect::Crossfade, 10, 10), px(NEW));
+}
+
+#[test]
+fn wipe_uncovers_from_the_left() {
+    let offscreen = run(Effect::Wipe, 4, 1);
+    for y in 0..8 {
+        for x in 0..16 {
+            let want = if x < 4 { NEW } else { OLD };
+            assert_eq!(pixel(&offscreen, x, y), px(want), "at ({}, {})", x, y);
+        }
+    }
+    check_all(&run(Effect::Wipe, 4, 4), px(NEW));
+}
+
+#[test]
+fn wipe_slides_the_old_scene_along() {
+    let mut offscreen = Offscreen::new(16, 8);
+    let mut screen = offscreen.screen(Vec2i(0, 0));
+    screen.clear(OLD);
+    // Mark the old scene's left edge so it can be followed
+    screen.fill_rect(
+        Rect {
+            x: 0,
+            y: 0,
+            w: 1,
+            h: 8,
+        },
+        BLACK,
+    );
+    let mut transition = Transition::new(Effect::Wipe, 4);
+    transition.capture(&screen);
+    screen.clear(NEW);
+    transition.update();
+    transition.draw(&mut screen);
+    for y in 0..8 {
+        assert_eq!(pixel(&offscreen, 3, y), px(NEW));
+        assert_eq!(pixel(&offscreen, 4, y), px(BLACK));
+        assert_eq!(pixel(&offscreen, 15, y), px(OLD));
+    }
+}
+
+#[test]
+fn iris_closes_then_opens() {
+    check_all(&run(Effect::Iris(BLACK), 10, 0), px(OLD));
+    check_all(&run(Effect::Iris(BLACK), 10, 5), px(BLACK));
+    check_all(&run(Effect::Iris(BLACK), 10, 10), px(NEW));
+    // Partway in, the middle still shows the old scene and the corners are covered
+    let closing = run(Effect::Iris(BLACK), 10, 3);
+    assert_eq!(pixel(&closing, 8, 4), px(OLD));
+    assert_eq!(pixel(&closing, 0, 0), px(BLACK));
+    assert_eq!(pixel(&closing, 15, 7), px(BLACK));
+    let opening = run(Effect::Iris(BLACK), 10, 7);
+    assert_eq!(pixel(&opening, 8, 4), px(NEW));
+    assert_eq!(pixel(&opening, 0, 7), px(BLACK));
+}
+
+// Just enough of a game to switch screens the way the games do: level 0 is the menu
+struct Game {
+    level: u16,
+    scene: u16,
+    transition: Option<Transition>,
+}
+
+impl Game {
+    fn draw(&self, screen: &mut Screen) {
+        screen.clear(if self.level == 0 { OLD } else { NEW });
+        if let Some(transition) = &self.transition {
+            transition.draw(screen);
+        }
+    }
+
+    // Update, then start a transition if that switched screens, then draw
+    fn frame(&mut self, dirty: &mut DirtyRects, offscreen: &mut Offscreen, start: bool) {
+        if start {
+            self.level = 1;
+        }
+        let mut screen = offscreen.screen(Vec2i(0, 0));
+        if self.level != self.scene {
+            self.scene = self.level;
+            self.transition = Some(Transition::new(Effect::Crossfade, 10));
+        }
+        if let Some(transition) = &mut self.transition {
+            transition.capture(&screen);
+        }
+        dirty.draw(&mut screen, |screen| self.draw(screen));
+    }
+}
+
+#[test]
+fn switching_screens_captures_the_one_being_left() {
+    let mut game = Game {
+        level: 0,
+        scene: 0,
+        transition: None,
+    };
+    let mut dirty = DirtyRects::new();
+    let mut offscreen = Offscreen::new(16, 8);
+    game.frame(&mut dirty, &mut offscreen, false);
+    check_all(&offscreen, px(OLD));
+    // The menu is still on screen when the level starts, so it's what fades away
+    game.frame(&mut dirty, &mut offscreen, true);
+    assert!(game.transition.is_some());
+    check_all(&offscreen, px(OLD));
+    for _ in 0..5 {
+        game.transition.as_mut().unwrap().update();
+    }
+    game.frame(&mut dirty, &mut offscreen, false);
+    check_all(&offscreen, [120, 40, 120, 255]);
+}